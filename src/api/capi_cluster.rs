@@ -1,48 +1,266 @@
-use std::collections::BTreeMap;
+use std::{borrow::Cow, collections::BTreeMap};
 
-use cluster_api_rs::capi_cluster::{ClusterSpec, ClusterStatus};
+use chrono::{DateTime, SecondsFormat, Utc};
 use fleet_api_rs::{
     fleet_bundle_namespace_mapping::{
         BundleNamespaceMappingBundleSelector, BundleNamespaceMappingNamespaceSelector,
     },
+    fleet_cluster::{ClusterAgentEnvVars, ClusterAgentTolerations},
     fleet_clustergroup::{ClusterGroupSelector, ClusterGroupSpec},
 };
-use k8s_openapi::api::core::v1::Namespace;
+use k8s_openapi::{NamespaceResourceScope, api::core::v1::Namespace};
 use kube::{
-    CustomResource, Resource, ResourceExt as _,
+    Resource, ResourceExt as _,
     api::{ObjectMeta, TypeMeta},
 };
-#[cfg(feature = "agent-initiated")]
 use rand::distr::{Alphanumeric, SampleString as _};
-use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
 
 use super::{
     bundle_namespace_mapping::BundleNamespaceMapping,
-    fleet_addon_config::ClusterConfig,
+    capi_contract::{CAPI_GROUP, CapiVersion},
+    fleet_addon_cluster_policy::{CLUSTER_POLICY_ANNOTATION, FleetAddonClusterPolicy},
+    fleet_addon_config::{ClusterConfig, DeletionPolicy, NamingResult, NamingValues},
     fleet_cluster,
+    fleet_cluster_registration_token::ClusterRegistrationToken,
     fleet_clustergroup::{CLUSTER_CLASS_LABEL, CLUSTER_CLASS_NAMESPACE_LABEL, ClusterGroup},
 };
 
-#[cfg(feature = "agent-initiated")]
-use super::fleet_cluster_registration_token::ClusterRegistrationToken;
-
 pub static FLEET_WORKSPACE_ANNOTATION: &str =
     "field.cattle.io/allow-fleetworkspace-creation-for-existing-namespace";
+pub static CLUSTER_NAME_LABEL: &str = "cluster-name.fleet.addons.cluster.x-k8s.io";
+pub static PAUSED_ANNOTATION: &str = "cluster.x-k8s.io/paused";
+pub static AGENT_INITIATED_ANNOTATION: &str = "agent-initiated.fleet.addons.cluster.x-k8s.io";
+pub static CLIENT_ID_ANNOTATION: &str = "client-id.fleet.addons.cluster.x-k8s.io";
+pub static REDEPLOY_AGENT_ANNOTATION: &str = "fleet.addons.cluster.x-k8s.io/redeploy-agent";
+pub static CAPI_CLUSTER_NAME_LABEL: &str = "cluster.x-k8s.io/cluster-name";
+pub static UPGRADE_GATE_ANNOTATION: &str = "upgrade-gate.fleet.addons.cluster.x-k8s.io";
+pub static DRAIN_ANNOTATION: &str = "drain.fleet.addons.cluster.x-k8s.io";
+pub static DELETION_POLICY_ANNOTATION: &str = "deletion-policy.fleet.addons.cluster.x-k8s.io";
+pub static AGENT_NAMESPACE_ANNOTATION: &str = "agent-namespace.fleet.addons.cluster.x-k8s.io";
+pub static AGENT_HOST_NETWORK_ANNOTATION: &str = "agent-host-network.fleet.addons.cluster.x-k8s.io";
+pub static AGENT_TOLERATIONS_ANNOTATION: &str = "agent-tolerations.fleet.addons.cluster.x-k8s.io";
+pub static AGENT_ENV_VARS_ANNOTATION: &str = "agent-env-vars.fleet.addons.cluster.x-k8s.io";
+
+pub static FLEET_IMPORTED_CONDITION: &str = "FleetImported";
+pub static FLEET_AGENT_READY_CONDITION: &str = "FleetAgentReady";
+pub static FLEET_BUNDLES_READY_CONDITION: &str = "FleetBundlesReady";
+pub static FLEET_AGENT_DELIVERED_CONDITION: &str = "FleetAgentDelivered";
+
+/// `ClusterCondition` is a condition on the CAPI Cluster status. It covers both the
+/// `cluster.x-k8s.io/v1beta1` condition format and the `metav1.Condition` format used by `v1beta2`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterCondition {
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_transition_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub observed_generation: Option<i64>,
+}
+
+impl ClusterCondition {
+    #[must_use]
+    pub fn ready(type_: &str, reason: &str) -> Self {
+        Self {
+            type_: type_.into(),
+            status: "True".into(),
+            reason: Some(reason.into()),
+            ..Default::default()
+        }
+    }
+
+    #[must_use]
+    pub fn not_ready(type_: &str, severity: &str, reason: &str, message: String) -> Self {
+        Self {
+            type_: type_.into(),
+            status: "False".into(),
+            severity: Some(severity.into()),
+            reason: Some(reason.into()),
+            message: Some(message),
+            ..Default::default()
+        }
+    }
+
+    #[must_use]
+    pub fn unknown(type_: &str, reason: &str, message: String) -> Self {
+        Self {
+            type_: type_.into(),
+            status: "Unknown".into(),
+            reason: Some(reason.into()),
+            message: Some(message),
+            ..Default::default()
+        }
+    }
+
+    /// `v1beta2` conditions require a reason and a message, track the observed
+    /// generation and have no severity.
+    fn for_version(mut self, version: CapiVersion, generation: Option<i64>) -> Self {
+        if version == CapiVersion::V1Beta2 {
+            self.severity = None;
+            if self.reason.is_none() {
+                self.reason = Some(self.status.clone());
+            }
+            self.message.get_or_insert_default();
+            self.observed_generation = generation;
+        }
+        self
+    }
+
+    fn same_state(&self, other: &Self) -> bool {
+        self.status == other.status
+            && self.severity == other.severity
+            && self.reason == other.reason
+            && self.message == other.message
+            && self.observed_generation == other.observed_generation
+    }
+}
+
+/// CAPI `Cluster`, served in the Cluster API version selected at startup.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Cluster {
+    #[serde(flatten, default)]
+    pub types: Option<TypeMeta>,
+    pub metadata: ObjectMeta,
+    #[serde(default)]
+    pub spec: ClusterSpec,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<ClusterStatus>,
+}
+
+impl Resource for Cluster {
+    type DynamicType = ();
+    type Scope = NamespaceResourceScope;
 
-/// `ClusterProxy` defines the desired state of the CAPI Cluster.
-#[derive(CustomResource, Serialize, Deserialize, Clone, Debug, JsonSchema)]
-#[kube(
-    group = "cluster.x-k8s.io",
-    version = "v1beta1",
-    kind = "Cluster",
-    plural = "clusters"
-)]
-#[kube(namespaced)]
-#[kube(status = "ClusterStatus")]
-pub struct ClusterProxy {
+    fn kind((): &()) -> Cow<'_, str> {
+        "Cluster".into()
+    }
+
+    fn group((): &()) -> Cow<'_, str> {
+        CAPI_GROUP.into()
+    }
+
+    fn version((): &()) -> Cow<'_, str> {
+        CapiVersion::selected().as_str().into()
+    }
+
+    fn plural((): &()) -> Cow<'_, str> {
+        "clusters".into()
+    }
+
+    fn meta(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn meta_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+}
+
+/// `ClusterSpec` is a version independent view of the CAPI Cluster spec, covering
+/// `v1beta1` and `v1beta2`. Fields not used by the controller are preserved as is.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paused: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub control_plane_ref: Option<ClusterReference>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub infrastructure_ref: Option<ClusterReference>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topology: Option<ClusterTopology>,
     #[serde(flatten)]
-    pub proxy: ClusterSpec,
+    pub other: BTreeMap<String, Value>,
+}
+
+/// Reference to a provider object. `v1beta1` references carry an `apiVersion` and a namespace,
+/// while `v1beta2` contract references only carry an `apiGroup`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterReference {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_group: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    #[serde(flatten)]
+    pub other: BTreeMap<String, Value>,
+}
+
+/// Cluster topology. `v1beta1` references the `ClusterClass` by `class` and `classNamespace`,
+/// `v1beta2` by `classRef`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterTopology {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class_namespace: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class_ref: Option<ClusterClassReference>,
+    #[serde(flatten)]
+    pub other: BTreeMap<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ClusterClassReference {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+}
+
+/// `ClusterStatus` is a version independent view of the CAPI Cluster status.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterStatus {
+    /// `v1beta1` control plane readiness
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub control_plane_ready: Option<bool>,
+    /// `v1beta2` initialization status
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initialization: Option<ClusterInitialization>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conditions: Option<Vec<ClusterCondition>>,
+    #[serde(flatten)]
+    pub other: BTreeMap<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterInitialization {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub control_plane_initialized: Option<bool>,
+    #[serde(flatten)]
+    pub other: BTreeMap<String, Value>,
+}
+
+/// Invalid per-cluster agent override, skipped when building the Fleet cluster.
+#[derive(Error, Debug)]
+pub enum AgentOverrideError {
+    #[error("`{0}` annotation is not valid YAML: {1}")]
+    Parse(&'static str, #[source] serde_yaml::Error),
+
+    #[error("`{0}` annotation must be `true` or `false`, got `{1}`")]
+    Bool(&'static str, String),
+
+    #[error("`{0}` annotation is not a valid namespace name: `{1}`")]
+    Namespace(&'static str, String),
 }
 
 impl From<&Cluster> for ObjectMeta {
@@ -79,7 +297,8 @@ impl Cluster {
                 name: Some(format!("{class}.{class_namespace}")),
                 namespace: self.namespace(),
                 labels: labels.clone(),
-                owner_references: self.owner_ref(&()).into_iter().map(Into::into).collect(),
+                owner_references: (self.deletion_policy(config) == DeletionPolicy::Delete)
+                    .then(|| self.owner_ref(&()).into_iter().collect()),
                 ..Default::default()
             },
             spec: ClusterGroupSpec {
@@ -95,15 +314,23 @@ impl Cluster {
     pub(crate) fn to_cluster(
         self: &Cluster,
         config: Option<&ClusterConfig>,
-    ) -> fleet_cluster::Cluster {
-        let empty = ClusterConfig::default();
-        let config = config.unwrap_or(&empty);
+        policy: Option<&FleetAddonClusterPolicy>,
+    ) -> NamingResult<fleet_cluster::Cluster> {
+        let mut config = config.cloned().unwrap_or_default();
         let class = self.cluster_class_name();
         let ns = self.namespace().unwrap_or_default();
         let class_namespace = self.cluster_class_namespace().unwrap_or(&ns);
-        let annotations = self.annotations().clone();
+        let mut annotations = self.annotations().clone();
+        if let Some(policy) = policy {
+            policy.spec.cluster.apply(&mut config);
+            annotations.insert(CLUSTER_POLICY_ANNOTATION.to_string(), policy.reference());
+        }
+        // Invalid overrides are reported by the cluster controller
+        let _ = self.apply_agent_overrides(&mut config);
+        let agent_initiated = self.agent_initiated(&config);
         let labels = {
             let mut labels = self.labels().clone();
+            labels.insert(CLUSTER_NAME_LABEL.to_string(), self.name_any());
             if let Some(class) = class {
                 labels.insert(CLUSTER_CLASS_LABEL.to_string(), class.to_string());
                 labels.insert(
@@ -114,49 +341,40 @@ impl Cluster {
             labels
         };
 
-        fleet_cluster::Cluster {
+        let name = config.apply_naming(&NamingValues {
+            name: &self.name_any(),
+            namespace: &ns,
+            cluster_class: class.unwrap_or_default(),
+        })?;
+
+        Ok(fleet_cluster::Cluster {
             types: Some(TypeMeta::resource::<fleet_cluster::Cluster>()),
             metadata: ObjectMeta {
                 annotations: Some(annotations),
                 labels: Some(labels),
-                owner_references: config
-                    .set_owner_references
-                    .is_some_and(|set| set)
+                owner_references: (config.set_owner_references.is_some_and(|set| set)
+                    && self.deletion_policy(Some(&config)) == DeletionPolicy::Delete)
                     .then_some(self.owner_ref(&()).into_iter().collect()),
-                name: config.apply_naming(self.name_any()).into(),
+                name: Some(name),
                 ..self.into()
             },
-            #[cfg(feature = "agent-initiated")]
-            spec: if config.agent_initiated_connection() {
-                fleet_api_rs::fleet_cluster::ClusterSpec {
-                    client_id: Some(Alphanumeric.sample_string(&mut rand::rng(), 64)),
-                    agent_namespace: config.agent_install_namespace().into(),
-                    agent_tolerations: config.agent_tolerations().into(),
-                    host_network: config.host_network,
-                    agent_env_vars: config.agent_env_vars.clone(),
-                    ..Default::default()
-                }
-            } else {
-                fleet_api_rs::fleet_cluster::ClusterSpec {
-                    kube_config_secret: Some(format!("{}-kubeconfig", self.name_any())),
-                    agent_namespace: config.agent_install_namespace().into(),
-                    agent_tolerations: config.agent_tolerations().into(),
-                    host_network: config.host_network,
-                    agent_env_vars: config.agent_env_vars.clone(),
-                    ..Default::default()
-                }
-            },
-            #[cfg(not(feature = "agent-initiated"))]
             spec: fleet_api_rs::fleet_cluster::ClusterSpec {
-                kube_config_secret: Some(format!("{}-kubeconfig", self.name_any())),
+                client_id: agent_initiated.then(|| self.client_id()),
+                kube_config_secret: (!agent_initiated)
+                    .then(|| config.kubeconfig_secret(&self.name_any())),
                 agent_namespace: config.agent_install_namespace().into(),
-                agent_tolerations: config.agent_tolerations().into(),
+                agent_tolerations: self.agent_tolerations(&config).into(),
                 host_network: config.host_network,
-                agent_env_vars: config.agent_env_vars.clone(),
+                agent_env_vars: self.agent_env_vars(&config),
+                agent_affinity: config.agent_affinity.clone(),
+                agent_resources: config.agent_resources.clone(),
+                agent_scheduling_customization: config.agent_scheduling_customization.clone(),
+                private_repo_url: config.private_repo_url.clone(),
+                paused: Some(self.paused()),
                 ..Default::default()
             },
             ..Default::default()
-        }
+        })
     }
 
     pub(crate) fn to_bundle_ns_mapping(
@@ -165,8 +383,7 @@ impl Cluster {
     ) -> Option<BundleNamespaceMapping> {
         config?.apply_class_group().then_some(true)?;
 
-        let topology = self.spec.proxy.topology.as_ref()?;
-        let class_namespace = topology.class_namespace.clone()?;
+        let class_namespace = self.cluster_class_namespace()?.to_string();
 
         let match_labels = {
             let mut labels = BTreeMap::default();
@@ -189,19 +406,18 @@ impl Cluster {
         })
     }
 
-    #[cfg(feature = "agent-initiated")]
     pub(crate) fn to_cluster_registration_token(
         self: &Cluster,
         config: Option<&ClusterConfig>,
     ) -> Option<ClusterRegistrationToken> {
         use fleet_api_rs::fleet_cluster_registration_token::ClusterRegistrationTokenSpec;
 
-        config?.agent_initiated?.then_some(true)?;
+        self.agent_initiated(config?).then_some(true)?;
 
         ClusterRegistrationToken {
             metadata: self.into(),
             spec: ClusterRegistrationTokenSpec {
-                ttl: Some("1h".into()),
+                ttl: config?.registration_token_ttl().into(),
             },
             ..Default::default()
         }
@@ -223,16 +439,900 @@ impl Cluster {
         }
     }
 
-    pub(crate) fn cluster_class_namespace(&self) -> Option<&str> {
+    /// Check if the agent-initiated registration is used for the cluster. The annotation
+    /// on the cluster takes precedence over the `agentInitiated` setting.
+    pub(crate) fn agent_initiated(&self, config: &ClusterConfig) -> bool {
+        match self
+            .annotations()
+            .get(AGENT_INITIATED_ANNOTATION)
+            .map(String::as_str)
+        {
+            Some("true") => true,
+            Some("false") => false,
+            _ => config.agent_initiated_connection(),
+        }
+    }
+
+    /// Deletion policy of the Fleet objects, from the deletion policy annotation or the config.
+    pub(crate) fn deletion_policy(&self, config: Option<&ClusterConfig>) -> DeletionPolicy {
+        match self
+            .annotations()
+            .get(DELETION_POLICY_ANNOTATION)
+            .map(String::as_str)
+        {
+            Some("Delete") => DeletionPolicy::Delete,
+            Some("Orphan") => DeletionPolicy::Orphan,
+            _ => config.and_then(|c| c.deletion_policy).unwrap_or_default(),
+        }
+    }
+
+    /// Agent client ID persisted on the cluster, or a newly generated one if not yet assigned.
+    pub(crate) fn client_id(&self) -> String {
+        self.annotations()
+            .get(CLIENT_ID_ANNOTATION)
+            .cloned()
+            .unwrap_or_else(|| Alphanumeric.sample_string(&mut rand::rng(), 64))
+    }
+
+    /// Apply the per-cluster agent overrides from the cluster annotations to the config.
+    /// Namespace and host network are replaced, tolerations are appended, and environment
+    /// variables are merged by name. The agent-initiated annotation is read by
+    /// `agent_initiated`, only its value is checked here. Invalid overrides are skipped
+    /// and returned.
+    pub(crate) fn apply_agent_overrides(
+        &self,
+        config: &mut ClusterConfig,
+    ) -> Vec<AgentOverrideError> {
+        let annotations = self.annotations();
+        let mut errors = vec![];
+
+        if let Some(namespace) = annotations.get(AGENT_NAMESPACE_ANNOTATION) {
+            let valid = !namespace.is_empty()
+                && namespace.len() <= 63
+                && namespace
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+                && !namespace.starts_with('-')
+                && !namespace.ends_with('-');
+            if valid {
+                config.agent_namespace = Some(namespace.clone());
+            } else {
+                errors.push(AgentOverrideError::Namespace(
+                    AGENT_NAMESPACE_ANNOTATION,
+                    namespace.clone(),
+                ));
+            }
+        }
+
+        match annotations
+            .get(AGENT_HOST_NETWORK_ANNOTATION)
+            .map(String::as_str)
+        {
+            Some("true") => config.host_network = Some(true),
+            Some("false") => config.host_network = Some(false),
+            Some(value) => errors.push(AgentOverrideError::Bool(
+                AGENT_HOST_NETWORK_ANNOTATION,
+                value.into(),
+            )),
+            None => {}
+        }
+
+        if let Some(value) = annotations
+            .get(AGENT_INITIATED_ANNOTATION)
+            .filter(|value| !matches!(value.as_str(), "true" | "false"))
+        {
+            errors.push(AgentOverrideError::Bool(
+                AGENT_INITIATED_ANNOTATION,
+                value.clone(),
+            ));
+        }
+
+        if let Some(tolerations) = annotations.get(AGENT_TOLERATIONS_ANNOTATION) {
+            match serde_yaml::from_str::<Vec<ClusterAgentTolerations>>(tolerations) {
+                Ok(tolerations) => {
+                    config.agent_tolerations =
+                        Some([config.agent_tolerations(), tolerations].concat());
+                }
+                Err(e) => errors.push(AgentOverrideError::Parse(AGENT_TOLERATIONS_ANNOTATION, e)),
+            }
+        }
+
+        if let Some(env_vars) = annotations.get(AGENT_ENV_VARS_ANNOTATION) {
+            match serde_yaml::from_str::<Vec<ClusterAgentEnvVars>>(env_vars) {
+                Ok(overrides) => {
+                    let mut env_vars: Vec<ClusterAgentEnvVars> = config
+                        .agent_env_vars
+                        .take()
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter(|var| overrides.iter().all(|o| o.name != var.name))
+                        .collect();
+                    env_vars.extend(overrides);
+                    config.agent_env_vars = Some(env_vars);
+                }
+                Err(e) => errors.push(AgentOverrideError::Parse(AGENT_ENV_VARS_ANNOTATION, e)),
+            }
+        }
+
+        errors
+    }
+
+    /// Target Kubernetes version of the managed topology.
+    pub(crate) fn topology_version(&self) -> Option<&str> {
         self.spec
-            .proxy
             .topology
             .as_ref()?
-            .class_namespace
-            .as_deref()
+            .other
+            .get("version")
+            .and_then(Value::as_str)
+    }
+
+    /// Check if a topology upgrade is in progress: the control plane does not report the
+    /// topology version yet, or the cluster reports a pending upgrade or an ongoing rollout.
+    pub(crate) fn upgrading(&self, control_plane_version: Option<&str>) -> bool {
+        let Some(version) = self.topology_version() else {
+            return false;
+        };
+
+        if control_plane_version.is_some_and(|current| current != version) {
+            return true;
+        }
+
+        let Some(status) = self.status.as_ref() else {
+            return false;
+        };
+        // `v1beta1` clusters report the `v1beta2` conditions in a separate list
+        let v1beta2_conditions: Vec<ClusterCondition> = status
+            .other
+            .get("v1beta2")
+            .and_then(|v1beta2| v1beta2.get("conditions"))
+            .and_then(|conditions| serde_json::from_value(conditions.clone()).ok())
+            .unwrap_or_default();
+
+        status
+            .conditions
+            .iter()
+            .flatten()
+            .chain(&v1beta2_conditions)
+            .any(|c| match c.type_.as_str() {
+                "RollingOut" => c.status == "True",
+                "TopologyReconciled" => {
+                    c.status == "False"
+                        && c.reason
+                            .as_deref()
+                            .is_some_and(|reason| reason.ends_with("UpgradePending"))
+                }
+                _ => false,
+            })
+    }
+
+    /// Check if the cluster topology defines schedulable workers. Worker topologies without
+    /// `replicas` are managed externally, e.g. by the autoscaler, and count as schedulable.
+    /// Returns `None` for clusters without a managed topology.
+    pub(crate) fn topology_workers(&self) -> Option<bool> {
+        let topology = self.spec.topology.as_ref()?;
+        let has_workers = ["machineDeployments", "machinePools"].iter().any(|kind| {
+            topology
+                .other
+                .get("workers")
+                .and_then(|workers| workers.get(kind))
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .any(|worker| worker.get("replicas").and_then(Value::as_i64) != Some(0))
+        });
+
+        Some(has_workers)
+    }
+
+    /// Agent tolerations, including control plane tolerations for clusters without workers
+    /// when topology tolerations are enabled.
+    pub(crate) fn agent_tolerations(&self, config: &ClusterConfig) -> Vec<ClusterAgentTolerations> {
+        let mut tolerations = config.agent_tolerations();
+        if config.topology_tolerations() && self.topology_workers() == Some(false) {
+            tolerations.extend(
+                [
+                    "node-role.kubernetes.io/control-plane",
+                    "node-role.kubernetes.io/master",
+                ]
+                .map(|key| ClusterAgentTolerations {
+                    effect: Some("NoSchedule".into()),
+                    operator: Some("Exists".into()),
+                    key: Some(key.into()),
+                    ..Default::default()
+                }),
+            );
+        }
+
+        tolerations
+    }
+
+    /// Hosts excluded from proxying for the cluster: the pod and service CIDRs, and the
+    /// control plane endpoint host.
+    pub(crate) fn no_proxy(&self) -> Vec<String> {
+        let spec = &self.spec.other;
+        let cidrs = |network: &str| {
+            spec.get("clusterNetwork")
+                .and_then(|n| n.pointer(&format!("/{network}/cidrBlocks")))
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect::<Vec<_>>()
+        };
+        let host = spec
+            .get("controlPlaneEndpoint")
+            .and_then(|endpoint| endpoint.get("host"))
+            .and_then(Value::as_str)
+            .filter(|host| !host.is_empty())
+            .map(String::from);
+
+        [cidrs("pods"), cidrs("services"), host.into_iter().collect()].concat()
+    }
+
+    /// Agent environment variables, including the proxy configuration. Variables set
+    /// explicitly in the agent environment take precedence.
+    pub(crate) fn agent_env_vars(
+        &self,
+        config: &ClusterConfig,
+    ) -> Option<Vec<ClusterAgentEnvVars>> {
+        let Some(proxy) = config.proxy.as_ref() else {
+            return config.agent_env_vars.clone();
+        };
+
+        let mut no_proxy = self.no_proxy();
+        for host in proxy.no_proxy.iter().flatten() {
+            if !no_proxy.contains(host) {
+                no_proxy.push(host.clone());
+            }
+        }
+
+        let env_var = |name: &str, value: String| ClusterAgentEnvVars {
+            name: name.into(),
+            value: Some(value),
+            ..Default::default()
+        };
+        let proxy_vars = [
+            proxy.http_proxy.clone().map(|p| env_var("HTTP_PROXY", p)),
+            proxy.https_proxy.clone().map(|p| env_var("HTTPS_PROXY", p)),
+            (!no_proxy.is_empty()).then(|| env_var("NO_PROXY", no_proxy.join(","))),
+        ];
+
+        let explicit = config.agent_env_vars.clone().unwrap_or_default();
+        let mut env_vars: Vec<ClusterAgentEnvVars> = proxy_vars
+            .into_iter()
+            .flatten()
+            .filter(|var| explicit.iter().all(|e| e.name != var.name))
+            .collect();
+        env_vars.extend(explicit);
+
+        Some(env_vars)
+    }
+
+    /// Check if the cluster is paused, either by `spec.paused` or by the paused annotation.
+    pub(crate) fn paused(&self) -> bool {
+        self.spec.paused.unwrap_or_default() || self.annotations().contains_key(PAUSED_ANNOTATION)
+    }
+
+    /// Start of the drain phase of the cluster deletion, if recorded.
+    pub(crate) fn drain_started(&self) -> Option<DateTime<Utc>> {
+        let started = self.annotations().get(DRAIN_ANNOTATION)?;
+        DateTime::parse_from_rfc3339(started).ok().map(Into::into)
+    }
+
+    pub(crate) fn cluster_class_namespace(&self) -> Option<&str> {
+        let topology = self.spec.topology.as_ref()?;
+        match topology.class_ref.as_ref() {
+            Some(class_ref) => class_ref.namespace.as_deref(),
+            None => topology.class_namespace.as_deref(),
+        }
     }
 
     pub(crate) fn cluster_class_name(&self) -> Option<&str> {
-        Some(&self.spec.proxy.topology.as_ref()?.class)
+        let topology = self.spec.topology.as_ref()?;
+        match topology.class_ref.as_ref() {
+            Some(class_ref) => Some(&class_ref.name),
+            None => topology.class.as_deref(),
+        }
+    }
+
+    /// Cluster API version of the object, defaulting to the served version.
+    pub(crate) fn capi_version(&self, served: CapiVersion) -> CapiVersion {
+        self.types
+            .as_ref()
+            .and_then(|t| CapiVersion::from_api_version(&t.api_version))
+            .unwrap_or(served)
+    }
+
+    /// Merge the conditions into the existing status conditions.
+    ///
+    /// Returns the full list of conditions only if any of the provided conditions changed,
+    /// keeping the transition time of conditions with an unchanged status.
+    pub(crate) fn merge_conditions(
+        &self,
+        served: CapiVersion,
+        conditions: &[ClusterCondition],
+    ) -> Option<Vec<ClusterCondition>> {
+        let version = self.capi_version(served);
+        let mut existing = self
+            .status
+            .as_ref()
+            .and_then(|s| s.conditions.clone())
+            .unwrap_or_default();
+
+        let now = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+        let mut changed = false;
+        for condition in conditions {
+            let condition = condition
+                .clone()
+                .for_version(version, self.metadata.generation);
+            let position = existing.iter().position(|c| c.type_ == condition.type_);
+            let current = position.map(|i| &existing[i]);
+            if current.is_some_and(|c| c.same_state(&condition)) {
+                continue;
+            }
+
+            let last_transition_time = match current {
+                Some(current) if current.status == condition.status => {
+                    current.last_transition_time.clone()
+                }
+                _ => Some(now.clone()),
+            };
+            let condition = ClusterCondition {
+                last_transition_time,
+                ..condition
+            };
+
+            changed = true;
+            match position {
+                Some(i) => existing[i] = condition,
+                None => existing.push(condition),
+            }
+        }
+
+        changed.then_some(existing)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+    use kube::ResourceExt as _;
+    use serde_json::json;
+
+    use crate::api::{
+        capi_contract::CapiVersion,
+        fleet_addon_config::{
+            ClusterConfig, DeletionPolicy, DrainConfig, NamingError, NamingStrategy,
+        },
+    };
+
+    use super::{
+        AGENT_ENV_VARS_ANNOTATION, AGENT_HOST_NETWORK_ANNOTATION, AGENT_INITIATED_ANNOTATION,
+        AGENT_NAMESPACE_ANNOTATION, AGENT_TOLERATIONS_ANNOTATION, CLIENT_ID_ANNOTATION, Cluster,
+        ClusterCondition, DELETION_POLICY_ANNOTATION, DRAIN_ANNOTATION,
+        FLEET_AGENT_READY_CONDITION, FLEET_IMPORTED_CONDITION, PAUSED_ANNOTATION,
+    };
+
+    #[test]
+    fn test_merge_conditions() {
+        let cluster: Cluster = serde_json::from_value(json!({
+            "apiVersion": "cluster.x-k8s.io/v1beta1",
+            "kind": "Cluster",
+            "metadata": {"name": "test", "namespace": "default"},
+            "spec": {},
+            "status": {
+                "conditions": [
+                    {"type": "Ready", "status": "True", "lastTransitionTime": "2024-01-01T00:00:00Z"},
+                    {"type": "FleetImported", "status": "True", "reason": "Imported", "lastTransitionTime": "2024-01-01T00:00:00Z"},
+                ],
+            },
+        }))
+        .unwrap();
+
+        let imported = ClusterCondition::ready(FLEET_IMPORTED_CONDITION, "Imported");
+        assert!(
+            cluster
+                .merge_conditions(CapiVersion::V1Beta2, std::slice::from_ref(&imported))
+                .is_none()
+        );
+
+        let agent = ClusterCondition::not_ready(
+            FLEET_AGENT_READY_CONDITION,
+            "Info",
+            "AgentNotConnected",
+            "Waiting for the fleet agent to connect".into(),
+        );
+        let conditions = cluster
+            .merge_conditions(CapiVersion::V1Beta2, &[imported, agent])
+            .unwrap();
+        assert_eq!(conditions.len(), 3);
+        assert_eq!(
+            conditions[1].last_transition_time.as_deref(),
+            Some("2024-01-01T00:00:00Z")
+        );
+        assert_eq!(conditions[2].type_, FLEET_AGENT_READY_CONDITION);
+        assert_eq!(conditions[2].reason.as_deref(), Some("AgentNotConnected"));
+        assert_eq!(conditions[2].severity.as_deref(), Some("Info"));
+        assert!(conditions[2].last_transition_time.is_some());
+    }
+
+    #[test]
+    fn test_v1beta2_cluster() {
+        let cluster: Cluster = serde_json::from_value(json!({
+            "apiVersion": "cluster.x-k8s.io/v1beta2",
+            "kind": "Cluster",
+            "metadata": {"name": "test", "namespace": "default", "generation": 3},
+            "spec": {
+                "controlPlaneRef": {
+                    "apiGroup": "controlplane.cluster.x-k8s.io",
+                    "kind": "KubeadmControlPlane",
+                    "name": "test-cp",
+                },
+                "topology": {
+                    "classRef": {"name": "quick-start", "namespace": "classes"},
+                    "version": "v1.33.0",
+                },
+            },
+            "status": {
+                "initialization": {"controlPlaneInitialized": true},
+                "conditions": [{
+                    "type": "Available",
+                    "status": "True",
+                    "reason": "Available",
+                    "message": "",
+                    "observedGeneration": 3,
+                    "lastTransitionTime": "2024-01-01T00:00:00Z",
+                }],
+            },
+        }))
+        .unwrap();
+
+        assert_eq!(cluster.cluster_class_name(), Some("quick-start"));
+        assert_eq!(cluster.cluster_class_namespace(), Some("classes"));
+        assert!(cluster.cluster_ready().is_some());
+
+        let control_plane_ref = cluster.spec.control_plane_ref.as_ref().unwrap();
+        assert_eq!(
+            control_plane_ref.api_group.as_deref(),
+            Some("controlplane.cluster.x-k8s.io")
+        );
+        assert!(control_plane_ref.api_version.is_none());
+
+        let agent = ClusterCondition::not_ready(
+            FLEET_AGENT_READY_CONDITION,
+            "Info",
+            "AgentNotConnected",
+            "Waiting for the fleet agent to connect".into(),
+        );
+        let conditions = cluster
+            .merge_conditions(CapiVersion::V1Beta1, std::slice::from_ref(&agent))
+            .unwrap();
+        assert_eq!(conditions[1].severity, None);
+        assert_eq!(conditions[1].observed_generation, Some(3));
+
+        let untyped = Cluster {
+            types: None,
+            ..cluster.clone()
+        };
+        let conditions = untyped
+            .merge_conditions(CapiVersion::V1Beta2, &[agent])
+            .unwrap();
+        assert_eq!(conditions[1].severity, None);
+
+        let serialized = serde_json::to_value(&cluster).unwrap();
+        assert_eq!(serialized["spec"]["topology"]["version"], "v1.33.0");
+        assert_eq!(serialized["apiVersion"], "cluster.x-k8s.io/v1beta2");
+    }
+
+    #[test]
+    fn test_agent_initiated_mode() {
+        let mut cluster: Cluster = serde_json::from_value(json!({
+            "metadata": {"name": "test", "namespace": "default"},
+        }))
+        .unwrap();
+        let mut config = ClusterConfig::default();
+
+        let fleet = cluster.to_cluster(Some(&config), None).unwrap();
+        assert_eq!(
+            fleet.spec.kube_config_secret.as_deref(),
+            Some("test-kubeconfig")
+        );
+        assert!(fleet.spec.client_id.is_none());
+        assert!(
+            cluster
+                .to_cluster_registration_token(Some(&config))
+                .is_none()
+        );
+
+        config.agent_initiated = Some(true);
+        let fleet = cluster.to_cluster(Some(&config), None).unwrap();
+        assert!(fleet.spec.kube_config_secret.is_none());
+        assert_eq!(fleet.spec.client_id.map(|id| id.len()), Some(64));
+        assert!(
+            cluster
+                .to_cluster_registration_token(Some(&config))
+                .is_some()
+        );
+
+        // The cluster annotation overrides the configured mode
+        cluster
+            .metadata
+            .annotations
+            .get_or_insert_default()
+            .insert(AGENT_INITIATED_ANNOTATION.into(), "false".into());
+        let fleet = cluster.to_cluster(Some(&config), None).unwrap();
+        assert!(fleet.spec.kube_config_secret.is_some());
+        assert!(
+            cluster
+                .to_cluster_registration_token(Some(&config))
+                .is_none()
+        );
+
+        // Invalid values fall back to the configured mode, and are reported
+        cluster
+            .metadata
+            .annotations
+            .get_or_insert_default()
+            .insert(AGENT_INITIATED_ANNOTATION.into(), "yes".into());
+        assert!(cluster.agent_initiated(&config));
+        let errors = cluster.apply_agent_overrides(&mut config.clone());
+        assert_eq!(
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            [format!(
+                "`{AGENT_INITIATED_ANNOTATION}` annotation must be `true` or `false`, got `yes`"
+            )]
+        );
+
+        config.agent_initiated = None;
+        cluster
+            .metadata
+            .annotations
+            .get_or_insert_default()
+            .insert(AGENT_INITIATED_ANNOTATION.into(), "true".into());
+        let fleet = cluster.to_cluster(Some(&config), None).unwrap();
+        assert!(fleet.spec.client_id.is_some());
+
+        // Persisted client ID is reused
+        cluster
+            .metadata
+            .annotations
+            .get_or_insert_default()
+            .insert(CLIENT_ID_ANNOTATION.into(), "stable-id".into());
+        let fleet = cluster.to_cluster(Some(&config), None).unwrap();
+        assert_eq!(fleet.spec.client_id.as_deref(), Some("stable-id"));
+
+        config.agent_initiated = Some(true);
+        config.registration_token_ttl = Some("30m".into());
+        let token = cluster
+            .to_cluster_registration_token(Some(&config))
+            .unwrap();
+        assert_eq!(token.spec.ttl.as_deref(), Some("30m"));
+    }
+
+    #[test]
+    fn test_agent_overrides() {
+        let cluster: Cluster = serde_json::from_value(json!({
+            "metadata": {
+                "name": "test",
+                "namespace": "default",
+                "annotations": {
+                    AGENT_NAMESPACE_ANNOTATION: "custom-agent",
+                    AGENT_HOST_NETWORK_ANNOTATION: "true",
+                    AGENT_TOLERATIONS_ANNOTATION: "- key: dedicated\n  operator: Exists\n",
+                    AGENT_ENV_VARS_ANNOTATION: r#"[{"name": "LOG_LEVEL", "value": "debug"}]"#,
+                },
+            },
+        }))
+        .unwrap();
+        let config: ClusterConfig = serde_json::from_value(json!({
+            "namespaceSelector": {},
+            "selector": {},
+            "agentEnvVars": [
+                {"name": "LOG_LEVEL", "value": "info"},
+                {"name": "HTTP_PROXY", "value": "http://proxy"},
+            ],
+        }))
+        .unwrap();
+
+        let fleet = cluster.to_cluster(Some(&config), None).unwrap();
+        assert_eq!(fleet.spec.agent_namespace.as_deref(), Some("custom-agent"));
+        assert_eq!(fleet.spec.host_network, Some(true));
+        let tolerations = fleet.spec.agent_tolerations.unwrap();
+        assert_eq!(tolerations.len(), config.agent_tolerations().len() + 1);
+        assert_eq!(
+            tolerations.last().unwrap().key.as_deref(),
+            Some("dedicated")
+        );
+        let env_vars = fleet.spec.agent_env_vars.unwrap();
+        assert_eq!(env_vars.len(), 2);
+        assert!(
+            env_vars
+                .iter()
+                .any(|var| var.name == "LOG_LEVEL" && var.value.as_deref() == Some("debug"))
+        );
+
+        let invalid: Cluster = serde_json::from_value(json!({
+            "metadata": {
+                "name": "test",
+                "annotations": {
+                    AGENT_NAMESPACE_ANNOTATION: "Invalid_Namespace",
+                    AGENT_HOST_NETWORK_ANNOTATION: "yes",
+                    AGENT_TOLERATIONS_ANNOTATION: "{not a list",
+                },
+            },
+        }))
+        .unwrap();
+        let mut config = ClusterConfig::default();
+        assert_eq!(invalid.apply_agent_overrides(&mut config).len(), 3);
+        // Invalid overrides keep the configured values
+        assert_eq!(config, ClusterConfig::default());
+    }
+
+    #[test]
+    fn test_proxy_env_vars() {
+        let cluster: Cluster = serde_json::from_value(json!({
+            "metadata": {"name": "test", "namespace": "default"},
+            "spec": {
+                "clusterNetwork": {
+                    "pods": {"cidrBlocks": ["192.168.0.0/16"]},
+                    "services": {"cidrBlocks": ["10.128.0.0/12"]},
+                },
+                "controlPlaneEndpoint": {"host": "172.18.0.3", "port": 6443},
+            },
+        }))
+        .unwrap();
+        let mut config: ClusterConfig = serde_json::from_value(json!({
+            "namespaceSelector": {},
+            "selector": {},
+            "proxy": {
+                "httpProxy": "http://proxy:3128",
+                "noProxy": ["localhost", "10.128.0.0/12"],
+            },
+        }))
+        .unwrap();
+
+        let env_vars = cluster.agent_env_vars(&config).unwrap();
+        let value = |name: &str| {
+            env_vars
+                .iter()
+                .find(|var| var.name == name)
+                .and_then(|var| var.value.clone())
+        };
+        assert_eq!(value("HTTP_PROXY").as_deref(), Some("http://proxy:3128"));
+        assert!(value("HTTPS_PROXY").is_none());
+        assert_eq!(
+            value("NO_PROXY").as_deref(),
+            Some("192.168.0.0/16,10.128.0.0/12,172.18.0.3,localhost")
+        );
+
+        // Explicit agent environment wins over the proxy settings
+        config.agent_env_vars = serde_json::from_value(json!([
+            {"name": "NO_PROXY", "value": "custom"},
+        ]))
+        .unwrap();
+        let env_vars = cluster.agent_env_vars(&config).unwrap();
+        assert_eq!(env_vars.len(), 2);
+        assert_eq!(env_vars[1].value.as_deref(), Some("custom"));
+    }
+
+    #[test]
+    fn test_topology_tolerations() {
+        let mut cluster: Cluster = serde_json::from_value(json!({
+            "metadata": {"name": "test", "namespace": "default"},
+            "spec": {
+                "topology": {
+                    "class": "quick-start",
+                    "version": "v1.33.0",
+                    "controlPlane": {"replicas": 1},
+                    "workers": {"machineDeployments": [{"class": "default-worker", "name": "md-0", "replicas": 0}]},
+                },
+            },
+        }))
+        .unwrap();
+        let mut config = ClusterConfig::default();
+        let defaults = config.agent_tolerations().len();
+        assert_eq!(cluster.topology_workers(), Some(false));
+        assert_eq!(cluster.agent_tolerations(&config).len(), defaults);
+
+        config.topology_tolerations = Some(true);
+        let fleet = cluster.to_cluster(Some(&config), None).unwrap();
+        let tolerations = fleet.spec.agent_tolerations.unwrap();
+        assert_eq!(tolerations.len(), defaults + 2);
+        assert!(
+            tolerations
+                .iter()
+                .any(|t| t.key.as_deref() == Some("node-role.kubernetes.io/control-plane"))
+        );
+
+        // Tolerations are removed once workers are added
+        cluster.spec.topology.as_mut().unwrap().other.insert(
+            "workers".into(),
+            json!({"machinePools": [{"class": "default-worker", "name": "mp-0"}]}),
+        );
+        assert_eq!(cluster.topology_workers(), Some(true));
+        assert_eq!(cluster.agent_tolerations(&config).len(), defaults);
+    }
+
+    #[test]
+    fn test_upgrading() {
+        let mut cluster: Cluster = serde_json::from_value(json!({
+            "metadata": {"name": "test", "namespace": "default"},
+            "spec": {"topology": {"class": "quick-start", "version": "v1.33.0"}},
+            "status": {"conditions": [
+                {"type": "TopologyReconciled", "status": "True", "reason": "ReconcileSucceeded"},
+            ]},
+        }))
+        .unwrap();
+        assert!(!cluster.upgrading(Some("v1.33.0")));
+        assert!(cluster.upgrading(Some("v1.32.4")));
+
+        cluster.status = serde_json::from_value(json!({
+            "conditions": [
+                {"type": "TopologyReconciled", "status": "False", "reason": "MachineDeploymentsUpgradePending"},
+            ],
+        }))
+        .unwrap();
+        assert!(cluster.upgrading(Some("v1.33.0")));
+
+        cluster.status = serde_json::from_value(json!({
+            "v1beta2": {"conditions": [
+                {"type": "RollingOut", "status": "True", "reason": "RollingOut", "message": ""},
+            ]},
+        }))
+        .unwrap();
+        assert!(cluster.upgrading(None));
+
+        cluster.spec.topology = None;
+        assert!(!cluster.upgrading(Some("v1.32.4")));
+    }
+
+    #[test]
+    fn test_paused() {
+        let mut cluster: Cluster = serde_json::from_value(json!({
+            "metadata": {"name": "test", "namespace": "default", "uid": "1234"},
+            "spec": {},
+        }))
+        .unwrap();
+        let config = ClusterConfig::default();
+        assert!(!cluster.paused());
+        assert_eq!(
+            cluster.to_cluster(Some(&config), None).unwrap().spec.paused,
+            Some(false)
+        );
+
+        cluster.spec.paused = Some(true);
+        assert!(cluster.paused());
+        assert_eq!(
+            cluster.to_cluster(Some(&config), None).unwrap().spec.paused,
+            Some(true)
+        );
+
+        // Paused with the annotation, as set by `clusterctl move`
+        cluster.spec.paused = None;
+        cluster
+            .annotations_mut()
+            .insert(PAUSED_ANNOTATION.into(), String::new());
+        assert!(cluster.paused());
+        assert_eq!(
+            cluster.to_cluster(Some(&config), None).unwrap().spec.paused,
+            Some(true)
+        );
+
+        // Resumed
+        cluster.annotations_mut().remove(PAUSED_ANNOTATION);
+        assert!(!cluster.paused());
+        assert_eq!(
+            cluster.to_cluster(Some(&config), None).unwrap().spec.paused,
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut cluster: Cluster = serde_json::from_value(json!({
+            "metadata": {"name": "test", "namespace": "default"},
+            "spec": {},
+        }))
+        .unwrap();
+        assert!(cluster.drain_started().is_none());
+
+        cluster
+            .annotations_mut()
+            .insert(DRAIN_ANNOTATION.into(), "invalid".into());
+        assert!(cluster.drain_started().is_none());
+
+        cluster
+            .annotations_mut()
+            .insert(DRAIN_ANNOTATION.into(), "2025-01-01T00:00:00+00:00".into());
+        let started = cluster.drain_started().unwrap();
+        assert_eq!(started.to_rfc3339(), "2025-01-01T00:00:00+00:00");
+
+        let drain = DrainConfig::default();
+        assert!(!drain.timed_out(started, started + TimeDelta::seconds(599)));
+        assert!(drain.timed_out(started, started + TimeDelta::seconds(600)));
+
+        let drain = DrainConfig {
+            timeout_seconds: Some(30),
+        };
+        assert!(drain.timed_out(started, started + TimeDelta::seconds(30)));
+    }
+
+    #[test]
+    fn test_naming_error() {
+        let cluster: Cluster = serde_json::from_value(json!({
+            "metadata": {"name": "test", "namespace": "default"},
+            "spec": {},
+        }))
+        .unwrap();
+        let config = ClusterConfig {
+            naming: Some(NamingStrategy {
+                template: Some("{{ .Labels }}".into()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        // The raw cluster name is not used as a fallback
+        assert_eq!(
+            cluster.to_cluster(Some(&config), None),
+            Err(NamingError::UnknownField(".Labels".into()))
+        );
+    }
+
+    #[test]
+    fn test_deletion_policy() {
+        let mut cluster: Cluster = serde_json::from_value(json!({
+            "metadata": {"name": "test", "namespace": "default", "uid": "1234"},
+            "spec": {},
+        }))
+        .unwrap();
+        let mut config = ClusterConfig::default();
+
+        assert_eq!(
+            cluster.deletion_policy(Some(&config)),
+            DeletionPolicy::Delete
+        );
+        assert_eq!(
+            cluster
+                .to_cluster(Some(&config), None)
+                .unwrap()
+                .owner_references()
+                .len(),
+            1
+        );
+
+        config.deletion_policy = Some(DeletionPolicy::Orphan);
+        assert_eq!(
+            cluster.deletion_policy(Some(&config)),
+            DeletionPolicy::Orphan
+        );
+        assert!(
+            cluster
+                .to_cluster(Some(&config), None)
+                .unwrap()
+                .owner_references()
+                .is_empty()
+        );
+
+        // The cluster annotation overrides the configured policy
+        cluster
+            .metadata
+            .annotations
+            .get_or_insert_default()
+            .insert(DELETION_POLICY_ANNOTATION.into(), "Delete".into());
+        assert_eq!(
+            cluster.deletion_policy(Some(&config)),
+            DeletionPolicy::Delete
+        );
+
+        config.deletion_policy = None;
+        cluster
+            .metadata
+            .annotations
+            .get_or_insert_default()
+            .insert(DELETION_POLICY_ANNOTATION.into(), "Orphan".into());
+        assert_eq!(cluster.deletion_policy(None), DeletionPolicy::Orphan);
     }
 }
@@ -1,28 +1,53 @@
-#[allow(unused_imports)]
-mod prelude {
-    pub use k8s_openapi::api::core::v1::ObjectReference;
-    pub use k8s_openapi::apimachinery::pkg::apis::meta::v1::Condition;
-    pub use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
-    pub use kube::CustomResource;
-    pub use schemars::JsonSchema;
-    pub use serde::{Deserialize, Serialize};
-    pub use std::collections::BTreeMap;
+use std::borrow::Cow;
+
+use k8s_openapi::NamespaceResourceScope;
+use kube::{
+    Resource,
+    api::{ObjectMeta, TypeMeta},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::capi_contract::{CAPI_GROUP, CapiVersion};
+
+/// CAPI `ClusterClass`, served in the Cluster API version selected at startup.
+/// The spec and status are kept opaque, as they differ between `v1beta1` and `v1beta2`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ClusterClass {
+    #[serde(flatten, default)]
+    pub types: Option<TypeMeta>,
+    pub metadata: ObjectMeta,
+    #[serde(default)]
+    pub spec: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<Value>,
 }
-use cluster_api_rs::capi_clusterclass::{ClusterClassSpec, ClusterClassStatus};
-
-use self::prelude::*;
-
-/// `ClusterClassProxy` describes the desired state of the `ClusterClass`.
-#[derive(CustomResource, Serialize, Deserialize, Clone, Debug, JsonSchema)]
-#[kube(
-    group = "cluster.x-k8s.io",
-    version = "v1beta1",
-    kind = "ClusterClass",
-    plural = "clusterclasses"
-)]
-#[kube(namespaced)]
-#[kube(status = "ClusterClassStatus")]
-pub struct ClusterClassProxy {
-    #[serde(flatten)]
-    pub proxy: ClusterClassSpec,
+
+impl Resource for ClusterClass {
+    type DynamicType = ();
+    type Scope = NamespaceResourceScope;
+
+    fn kind((): &()) -> Cow<'_, str> {
+        "ClusterClass".into()
+    }
+
+    fn group((): &()) -> Cow<'_, str> {
+        CAPI_GROUP.into()
+    }
+
+    fn version((): &()) -> Cow<'_, str> {
+        CapiVersion::selected().as_str().into()
+    }
+
+    fn plural((): &()) -> Cow<'_, str> {
+        "clusterclasses".into()
+    }
+
+    fn meta(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn meta_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
 }
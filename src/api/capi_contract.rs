@@ -0,0 +1,158 @@
+use std::sync::OnceLock;
+
+use k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition;
+use kube::{
+    Api, Client, ResourceExt as _,
+    api::{ApiResource, GroupVersionKind},
+    discovery,
+};
+use thiserror::Error;
+use tracing::{info, warn};
+
+pub static CAPI_GROUP: &str = "cluster.x-k8s.io";
+
+/// Version bound to the typed `Cluster` and `ClusterClass` resources, see [`CapiVersion::select`].
+static RESOURCE_VERSION: OnceLock<CapiVersion> = OnceLock::new();
+
+pub type ContractResult<T, E = ContractError> = std::result::Result<T, E>;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("API discovery error: {0}")]
+    Discovery(#[source] kube::Error),
+
+    #[error("Kind {1} is not served in the API group {0}")]
+    UnknownKind(String, String),
+
+    #[error("CustomResourceDefinition lookup error: {0}")]
+    Lookup(#[source] kube::Error),
+}
+
+/// Cluster API version used for the `Cluster` and `ClusterClass` resources.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CapiVersion {
+    #[default]
+    V1Beta1,
+    V1Beta2,
+}
+
+impl CapiVersion {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::V1Beta1 => "v1beta1",
+            Self::V1Beta2 => "v1beta2",
+        }
+    }
+
+    /// Parse the version from an `apiVersion` in the `cluster.x-k8s.io` group.
+    #[must_use]
+    pub fn from_api_version(api_version: &str) -> Option<Self> {
+        match api_version.strip_prefix(CAPI_GROUP)?.strip_prefix('/')? {
+            "v1beta1" => Some(Self::V1Beta1),
+            "v1beta2" => Some(Self::V1Beta2),
+            _ => None,
+        }
+    }
+
+    /// Version of the typed `Cluster` and `ClusterClass` resources, `v1beta1` until
+    /// [`CapiVersion::select`] is called.
+    #[must_use]
+    pub(crate) fn selected() -> Self {
+        RESOURCE_VERSION.get().copied().unwrap_or_default()
+    }
+
+    /// Bind the typed `Cluster` and `ClusterClass` resources to this version for the lifetime
+    /// of the process. `kube::Resource` resolves the version without an object or a context,
+    /// so this is kept process-wide; everything else receives the version from the controller
+    /// `Context`. Only the first selection applies.
+    pub fn select(self) {
+        if RESOURCE_VERSION.set(self).is_ok() {
+            info!("Using Cluster API {}", self.as_str());
+        }
+    }
+
+    /// Discover the most recent Cluster API version served by the API server.
+    /// Falls back to `v1beta1` if the Cluster API group can't be discovered.
+    pub async fn discover(client: &Client) -> Self {
+        match discovery::group(client, CAPI_GROUP).await {
+            Ok(group) if group.versions().any(|v| v == Self::V1Beta2.as_str()) => Self::V1Beta2,
+            Ok(_) => Self::V1Beta1,
+            Err(e) => {
+                warn!("Failed to discover Cluster API versions, assuming v1beta1: {e}");
+                Self::V1Beta1
+            }
+        }
+    }
+
+    /// Label set on provider CRDs, listing the CRD versions compatible with this contract.
+    fn contract_label(self) -> String {
+        format!("{CAPI_GROUP}/{}", self.as_str())
+    }
+}
+
+/// Resolve the `ApiResource` of a contract reference, which only carries an API group and a kind.
+///
+/// The version is taken from the `capi_version` contract label on the referenced CRD, where the
+/// value lists compatible versions separated by `_`, the latest one last. If the CRD has no
+/// contract label, the preferred version of the API group is used.
+///
+/// # Errors
+///
+/// This function will return an error if the API group can't be discovered, the kind is not
+/// served, or the CRD can't be read.
+pub async fn resolve_contract_reference(
+    client: Client,
+    capi_version: CapiVersion,
+    api_group: &str,
+    kind: &str,
+) -> ContractResult<ApiResource> {
+    let group = discovery::group(&client, api_group)
+        .await
+        .map_err(ContractError::Discovery)?;
+    let (resource, _) = group
+        .recommended_kind(kind)
+        .ok_or_else(|| ContractError::UnknownKind(api_group.into(), kind.into()))?;
+
+    let crd_name = format!("{}.{api_group}", resource.plural);
+    let crd = Api::<CustomResourceDefinition>::all(client)
+        .get_metadata_opt(&crd_name)
+        .await
+        .map_err(ContractError::Lookup)?;
+
+    let contract_version = crd.and_then(|crd| {
+        crd.labels()
+            .get(&capi_version.contract_label())
+            .and_then(|versions| versions.split('_').next_back())
+            .map(String::from)
+    });
+
+    Ok(match contract_version {
+        Some(version) => ApiResource::from_gvk_with_plural(
+            &GroupVersionKind::gvk(api_group, &version, kind),
+            &resource.plural,
+        ),
+        None => resource,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CapiVersion;
+
+    #[test]
+    fn test_from_api_version() {
+        assert_eq!(
+            CapiVersion::from_api_version("cluster.x-k8s.io/v1beta2"),
+            Some(CapiVersion::V1Beta2)
+        );
+        assert_eq!(
+            CapiVersion::from_api_version("cluster.x-k8s.io/v1beta1"),
+            Some(CapiVersion::V1Beta1)
+        );
+        assert_eq!(
+            CapiVersion::from_api_version("fleet.cattle.io/v1beta2"),
+            None
+        );
+    }
+}
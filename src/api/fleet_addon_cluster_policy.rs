@@ -0,0 +1,323 @@
+use std::{cmp::Reverse, collections::BTreeMap, sync::Arc};
+
+use fleet_api_rs::fleet_cluster::{ClusterAgentEnvVars, ClusterAgentTolerations};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+use kube::{
+    CustomResource, ResourceExt as _,
+    core::{Selector, SelectorExt as _},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use super::{
+    capi_cluster::Cluster,
+    fleet_addon_config::{ClusterConfig, ConfigValidationError, NamingStrategy, UpgradeGate},
+};
+
+pub static CLUSTER_POLICY_ANNOTATION: &str = "cluster-policy.fleet.addons.cluster.x-k8s.io";
+
+/// This provides a per-selector overlay for the `FleetAddonConfig` cluster settings
+#[derive(CustomResource, Deserialize, Serialize, Clone, Default, Debug, JsonSchema, PartialEq)]
+#[kube(
+    kind = "FleetAddonClusterPolicy",
+    group = "addons.cluster.x-k8s.io",
+    version = "v1alpha1",
+    namespaced,
+    printcolumn = r#"{"name":"Priority","type":"integer","jsonPath":".spec.priority"}"#
+)]
+#[serde(rename_all = "camelCase")]
+pub struct FleetAddonClusterPolicySpec {
+    /// Policy priority. When multiple policies select the same cluster, the policy with
+    /// the highest priority wins. Ties are resolved by the policy namespace and name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
+
+    /// Namespace label selector. If not set, only clusters in the policy namespace are selected.
+    /// Only allowed for policies in the controller namespace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace_selector: Option<LabelSelector>,
+
+    /// Cluster label selector. If not set, all clusters in the selected namespaces are selected.
+    #[serde(default)]
+    pub selector: LabelSelector,
+
+    /// Cluster settings overriding the `FleetAddonConfig` cluster settings for selected clusters.
+    pub cluster: ClusterConfigOverride,
+}
+
+/// `ClusterConfigOverride` is a partial `ClusterConfig`. Every set field replaces
+/// the corresponding `FleetAddonConfig` cluster setting.
+#[derive(Serialize, Deserialize, Clone, Default, Debug, JsonSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterConfigOverride {
+    /// Naming settings for the fleet cluster
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub naming: Option<NamingStrategy>,
+
+    /// Namespace selection for the fleet agent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_namespace: Option<String>,
+
+    /// Agent taint toleration settings for selected clusters
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_tolerations: Option<Vec<ClusterAgentTolerations>>,
+
+    /// Host network allows to deploy agent configuration using hostNetwork: true setting
+    /// which eludes dependency on the CNI configuration for the cluster.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host_network: Option<bool>,
+
+    /// `AgentEnvVars` are extra environment variables to be added to the agent deployment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_env_vars: Option<Vec<ClusterAgentEnvVars>>,
+
+    /// Readiness gate for CAPI topology upgrades of selected clusters.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upgrade_gate: Option<UpgradeGate>,
+}
+
+impl ClusterConfigOverride {
+    /// Overlay the set fields on top of the provided `ClusterConfig`.
+    pub(crate) fn apply(&self, config: &mut ClusterConfig) {
+        if let Some(naming) = &self.naming {
+            config.naming = Some(naming.clone());
+        }
+        if let Some(agent_namespace) = &self.agent_namespace {
+            config.agent_namespace = Some(agent_namespace.clone());
+        }
+        if let Some(agent_tolerations) = &self.agent_tolerations {
+            config.agent_tolerations = Some(agent_tolerations.clone());
+        }
+        if let Some(host_network) = self.host_network {
+            config.host_network = Some(host_network);
+        }
+        if let Some(agent_env_vars) = &self.agent_env_vars {
+            config.agent_env_vars = Some(agent_env_vars.clone());
+        }
+        if let Some(upgrade_gate) = &self.upgrade_gate {
+            config.upgrade_gate = Some(upgrade_gate.clone());
+        }
+    }
+}
+
+impl FleetAddonClusterPolicy {
+    /// Policy reference in the `namespace/name` form, reported on the imported cluster.
+    pub(crate) fn reference(&self) -> String {
+        format!(
+            "{}/{}",
+            self.namespace().unwrap_or_default(),
+            self.name_any()
+        )
+    }
+
+    /// Validate the settings which can't be expressed in the CRD schema. Policies outside of
+    /// the controller namespace can't select clusters in other namespaces.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigValidationError` listing every invalid setting.
+    pub fn validate(&self, controller_namespace: &str) -> Result<(), ConfigValidationError> {
+        let mut errors = vec![];
+
+        if let Some(selector) = &self.spec.namespace_selector {
+            if self.namespace().as_deref() != Some(controller_namespace) {
+                errors.push(format!(
+                    "spec.namespaceSelector: only allowed in the `{controller_namespace}` namespace"
+                ));
+            }
+            if let Err(e) = Selector::try_from(selector.clone()) {
+                errors.push(format!("spec.namespaceSelector: {e}"));
+            }
+        }
+        if let Err(e) = Selector::try_from(self.spec.selector.clone()) {
+            errors.push(format!("spec.selector: {e}"));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigValidationError(errors))
+        }
+    }
+
+    /// Check if the policy selects the cluster, given the labels of the cluster namespace.
+    /// Namespace selectors of policies outside of the controller namespace are ignored.
+    pub(crate) fn selects(
+        &self,
+        cluster: &Cluster,
+        ns_labels: &BTreeMap<String, String>,
+        controller_namespace: &str,
+    ) -> bool {
+        let namespace_match = match &self.spec.namespace_selector {
+            Some(_) if self.namespace().as_deref() != Some(controller_namespace) => {
+                warn!(
+                    "Skipping policy {}: namespaceSelector is only allowed in the `{controller_namespace}` namespace",
+                    self.reference()
+                );
+                return false;
+            }
+            Some(selector) => match Selector::try_from(selector.clone()) {
+                Ok(selector) => selector.matches(ns_labels),
+                Err(e) => {
+                    warn!("Skipping policy {}: {e}", self.reference());
+                    return false;
+                }
+            },
+            None => self.namespace() == cluster.namespace(),
+        };
+
+        namespace_match
+            && match Selector::try_from(self.spec.selector.clone()) {
+                Ok(selector) => selector.matches(cluster.labels()),
+                Err(e) => {
+                    warn!("Skipping policy {}: {e}", self.reference());
+                    false
+                }
+            }
+    }
+
+    /// Find the policy with the highest precedence selecting the cluster.
+    ///
+    /// Higher `priority` wins, ties are resolved by the lowest `namespace/name`.
+    pub(crate) fn resolve<'a>(
+        policies: &'a [Arc<FleetAddonClusterPolicy>],
+        cluster: &Cluster,
+        ns_labels: &BTreeMap<String, String>,
+        controller_namespace: &str,
+    ) -> Option<&'a FleetAddonClusterPolicy> {
+        policies
+            .iter()
+            .filter(|p| p.metadata.deletion_timestamp.is_none())
+            .filter(|p| p.selects(cluster, ns_labels, controller_namespace))
+            .min_by_key(|p| (Reverse(p.spec.priority.unwrap_or_default()), p.reference()))
+            .map(AsRef::as_ref)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::Arc};
+
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+    use kube::api::ObjectMeta;
+    use serde_json::json;
+
+    use crate::api::capi_cluster::Cluster;
+
+    use super::{ClusterConfigOverride, FleetAddonClusterPolicy, FleetAddonClusterPolicySpec};
+
+    fn policy(
+        name: &str,
+        namespace: &str,
+        priority: Option<i32>,
+        selector: LabelSelector,
+    ) -> FleetAddonClusterPolicy {
+        FleetAddonClusterPolicy {
+            metadata: ObjectMeta {
+                name: Some(name.into()),
+                namespace: Some(namespace.into()),
+                ..Default::default()
+            },
+            spec: FleetAddonClusterPolicySpec {
+                priority,
+                selector,
+                cluster: ClusterConfigOverride::default(),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn edge_selector() -> LabelSelector {
+        LabelSelector {
+            match_labels: Some(BTreeMap::from([("site".into(), "edge".into())])),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_resolve_precedence() {
+        let cluster: Cluster = serde_json::from_value(json!({
+            "apiVersion": "cluster.x-k8s.io/v1beta1",
+            "kind": "Cluster",
+            "metadata": {
+                "name": "edge-1",
+                "namespace": "clusters",
+                "labels": {"site": "edge"},
+            },
+            "spec": {},
+        }))
+        .unwrap();
+
+        let policies = [
+            policy("default", "clusters", None, LabelSelector::default()),
+            policy("b-edge", "clusters", Some(10), edge_selector()),
+            policy("a-edge", "clusters", Some(10), edge_selector()),
+            policy("other-namespace", "other", Some(100), edge_selector()),
+        ]
+        .map(Arc::new);
+
+        let winner =
+            FleetAddonClusterPolicy::resolve(&policies, &cluster, &BTreeMap::new(), "caapf-system");
+        assert_eq!(
+            Some("clusters/a-edge".to_string()),
+            winner.map(FleetAddonClusterPolicy::reference)
+        );
+
+        let policies = [Arc::new(policy(
+            "default",
+            "clusters",
+            None,
+            LabelSelector::default(),
+        ))];
+        let winner =
+            FleetAddonClusterPolicy::resolve(&policies, &cluster, &BTreeMap::new(), "caapf-system");
+        assert_eq!(
+            Some("clusters/default".to_string()),
+            winner.map(FleetAddonClusterPolicy::reference)
+        );
+
+        let datacenter_selector = LabelSelector {
+            match_labels: Some(BTreeMap::from([("site".into(), "dc".into())])),
+            ..Default::default()
+        };
+        let policies = [Arc::new(policy(
+            "datacenter",
+            "clusters",
+            Some(1),
+            datacenter_selector,
+        ))];
+        assert!(
+            FleetAddonClusterPolicy::resolve(&policies, &cluster, &BTreeMap::new(), "caapf-system")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_namespace_selector_scope() {
+        let cluster: Cluster = serde_json::from_value(json!({
+            "apiVersion": "cluster.x-k8s.io/v1beta1",
+            "kind": "Cluster",
+            "metadata": {"name": "edge-1", "namespace": "clusters"},
+            "spec": {},
+        }))
+        .unwrap();
+        let ns_labels = BTreeMap::from([("site".into(), "edge".into())]);
+
+        let mut controller = policy("edge", "caapf-system", None, LabelSelector::default());
+        controller.spec.namespace_selector = Some(edge_selector());
+        let mut other = policy("edge", "other", Some(10), LabelSelector::default());
+        other.spec.namespace_selector = Some(edge_selector());
+
+        assert!(controller.validate("caapf-system").is_ok());
+        assert_eq!(1, other.validate("caapf-system").unwrap_err().0.len());
+
+        let policies = [controller, other].map(Arc::new);
+        let winner =
+            FleetAddonClusterPolicy::resolve(&policies, &cluster, &ns_labels, "caapf-system");
+        assert_eq!(
+            Some("caapf-system/edge".to_string()),
+            winner.map(FleetAddonClusterPolicy::reference)
+        );
+    }
+}
@@ -1,23 +1,37 @@
-use std::{fmt::Display, str::FromStr};
+use std::{collections::BTreeMap, fmt::Display, str::FromStr};
 
 use crate::api::comparable::ResourceDiff;
+use chrono::{DateTime, TimeDelta, Utc};
 use educe::Educe;
-use fleet_api_rs::fleet_cluster::{ClusterAgentEnvVars, ClusterAgentTolerations};
+use fleet_api_rs::fleet_cluster::{
+    ClusterAgentAffinity, ClusterAgentEnvVars, ClusterAgentResources,
+    ClusterAgentSchedulingCustomization, ClusterAgentTolerations,
+};
 use k8s_openapi::{
-    api::core::v1::{ConfigMap, ObjectReference},
+    api::{
+        core::v1::{ConfigMap, ObjectReference},
+        rbac::v1::PolicyRule,
+    },
     apimachinery::pkg::apis::meta::v1::{Condition, LabelSelector},
 };
 use kube::{
-    CustomResource, KubeSchema, Resource,
+    CustomResource, KubeSchema, Resource, ResourceExt,
     api::{ObjectMeta, TypeMeta},
-    core::{ParseExpressionError, Selector},
+    core::{ParseExpressionError, Selector, SelectorExt as _},
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize, ser};
 use serde_with::{DisplayFromStr, serde_as};
 use serde_yaml::Value;
+use thiserror::Error;
 
 pub const AGENT_NAMESPACE: &str = "fleet-addon-agent";
+pub const REGISTRATION_TOKEN_TTL: &str = "1h";
+pub const UPGRADE_GATE_TIMEOUT_SECONDS: i64 = 3600;
+pub const UPGRADE_GATE_RETRY_AFTER_SECONDS: i32 = 30;
+pub const DRAIN_TIMEOUT_SECONDS: i64 = 600;
+pub const ADOPTION_KEY: &str = "adopt.fleet.addons.cluster.x-k8s.io";
+pub const SCOPED_TOKEN_EXPIRATION_SECONDS: i64 = 86400;
 pub const EXPERIMENTAL_OCI_STORAGE: &str = "EXPERIMENTAL_OCI_STORAGE";
 pub const EXPERIMENTAL_HELM_OPS: &str = "EXPERIMENTAL_HELM_OPS";
 
@@ -139,15 +153,100 @@ pub struct ClusterConfig {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub agent_env_vars: Option<Vec<ClusterAgentEnvVars>>,
 
+    /// Agent affinity settings for every cluster. If not set, Fleet applies its default
+    /// affinity, preferring nodes labelled with `fleet.cattle.io/agent`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_affinity: Option<ClusterAgentAffinity>,
+
+    /// Resource requests and limits of the agent container.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_resources: Option<ClusterAgentResources>,
+
+    /// Agent scheduling customization, creating a `PriorityClass` and a `PodDisruptionBudget`
+    /// for the agent deployment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_scheduling_customization: Option<ClusterAgentSchedulingCustomization>,
+
+    /// Pause the Fleet cluster while CAPI upgrades the cluster topology version, and resume
+    /// it once the control plane and workers are rolled out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pause_during_upgrade: Option<bool>,
+
+    /// Add control plane tolerations to the agent of clusters whose topology has no worker
+    /// replicas, so the agent can be scheduled on control plane nodes. The tolerations are
+    /// removed once workers are added to the topology.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topology_tolerations: Option<bool>,
+
+    /// HTTP proxy settings for the fleet agent. `NO_PROXY` is extended with the pod and
+    /// service CIDRs and the control plane endpoint host of each cluster.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ProxyConfig>,
+
+    /// Private registry prefix for the agent image, e.g. `registry.example.com`.
+    #[serde(
+        default,
+        rename = "privateRepoURL",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub private_repo_url: Option<String>,
+
     /// Import settings for the CAPI cluster. Allows to import clusters based on a set of labels,
     /// set on the cluster or the namespace.
     #[serde(flatten)]
     pub selectors: Selectors,
 
-    #[cfg(feature = "agent-initiated")]
-    /// Prepare initial cluster for agent initiated connection
+    /// Un-import settings for imported clusters which no longer match the import selectors.
+    /// If not set, fleet resources of such clusters are retained.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unimport: Option<UnimportConfig>,
+
+    /// Adoption settings for Fleet clusters registered before the CAPI cluster was imported.
+    /// If not set, a Fleet cluster with the same name is taken over, and Fleet clusters
+    /// registered under a different name are left in place.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adoption: Option<AdoptionConfig>,
+
+    /// Deletion policy for the Fleet objects of deleted CAPI clusters. With `Orphan`, owner
+    /// references to the CAPI cluster are removed, and the Fleet `Cluster`, `ClusterGroup` and
+    /// `BundleNamespaceMapping` are left in place. Defaults to `Delete`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deletion_policy: Option<DeletionPolicy>,
+
+    /// Drain the cluster from Fleet before the CAPI cluster is deleted. The cluster is removed
+    /// from Fleet targets, and the deletion is held until its bundle deployments are removed.
+    /// If not set, the Fleet cluster is removed together with the CAPI cluster.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drain: Option<DrainConfig>,
+
+    /// Prepare initial cluster for agent initiated connection, instead of deploying the agent
+    /// with the CAPI cluster kubeconfig secret. Can be overridden per cluster with the
+    /// `agent-initiated.fleet.addons.cluster.x-k8s.io` annotation.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub agent_initiated: Option<bool>,
+
+    /// Deliver the fleet agent registration into agent initiated clusters, once the control
+    /// plane is initialized. `kubeconfig` applies the agent manifests with the CAPI cluster
+    /// kubeconfig secret, `clusterResourceSet` creates a CAPI `ClusterResourceSet` for them.
+    /// If not set, the fleet agent has to be installed manually.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_delivery: Option<AgentDelivery>,
+
+    /// Time to live of the `ClusterRegistrationToken` created for agent initiated clusters,
+    /// e.g. `30m` or `24h`. Expired tokens are regenerated until the agent registers.
+    /// Defaults to `1h`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registration_token_ttl: Option<String>,
+
+    /// Hold back CAPI topology upgrades with the `BeforeClusterUpgrade` runtime hook, until
+    /// the fleet agent is connected and all bundle deployments targeting the cluster are ready.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upgrade_gate: Option<UpgradeGate>,
+
+    /// Bootstrap a dedicated ServiceAccount in the workload cluster, and reference its
+    /// kubeconfig in the Fleet cluster instead of the CAPI cluster-admin kubeconfig.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scoped_kubeconfig: Option<ScopedKubeconfig>,
 }
 
 #[derive(Resource, Serialize, Deserialize, Default, Clone, Debug)]
@@ -237,14 +336,30 @@ impl ClusterConfig {
         self.agent_tolerations.clone().unwrap_or(agent_tolerations)
     }
 
-    #[cfg(feature = "agent-initiated")]
+    pub(crate) fn topology_tolerations(&self) -> bool {
+        self.topology_tolerations.is_some_and(|enabled| enabled)
+    }
+
     pub(crate) fn agent_initiated_connection(&self) -> bool {
         self.agent_initiated.filter(|&set| set).is_some()
     }
 
-    pub(crate) fn apply_naming(&self, name: String) -> String {
-        let strategy = self.naming.clone().unwrap_or_default();
-        strategy.apply(name.clone().into()).unwrap_or(name)
+    pub(crate) fn registration_token_ttl(&self) -> String {
+        self.registration_token_ttl
+            .clone()
+            .unwrap_or(REGISTRATION_TOKEN_TTL.to_string())
+    }
+
+    /// Name of the kubeconfig secret referenced by the Fleet cluster.
+    pub(crate) fn kubeconfig_secret(&self, cluster_name: &str) -> String {
+        match self.scoped_kubeconfig {
+            Some(_) => format!("{cluster_name}-fleet-kubeconfig"),
+            None => format!("{cluster_name}-kubeconfig"),
+        }
+    }
+
+    pub(crate) fn apply_naming(&self, values: &NamingValues) -> NamingResult<String> {
+        self.naming.clone().unwrap_or_default().render(values)
     }
 
     pub(crate) fn apply_class_group(&self) -> bool {
@@ -254,11 +369,44 @@ impl ClusterConfig {
 
 /// `NamingStrategy` is controlling Fleet cluster naming
 #[derive(Serialize, Deserialize, Clone, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
 pub struct NamingStrategy {
     /// Specify a prefix for the Cluster name, applied to created Fleet cluster
     pub prefix: Option<String>,
     /// Specify a suffix for the Cluster name, applied to created Fleet cluster
     pub suffix: Option<String>,
+    /// Specify a template for the Cluster name, applied to created Fleet cluster.
+    /// Supported fields are `{{ .Name }}`, `{{ .Namespace }}` and `{{ .ClusterClass }}`.
+    /// Prefix and suffix are applied to the rendered template.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// Maximum length of the Fleet cluster name. Longer names are truncated
+    /// and suffixed with a stable short hash of the full name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(range(min = 16, max = 253))]
+    pub max_length: Option<usize>,
+}
+
+/// `NamingValues` are the CAPI cluster values available to the naming template
+#[derive(Clone, Debug, Default)]
+pub struct NamingValues<'a> {
+    pub name: &'a str,
+    pub namespace: &'a str,
+    pub cluster_class: &'a str,
+}
+
+pub type NamingResult<T> = std::result::Result<T, NamingError>;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum NamingError {
+    #[error("Unclosed template expression in `{0}`")]
+    Unclosed(String),
+
+    #[error("Unknown template field `{0}`, expected one of .Name, .Namespace, .ClusterClass")]
+    UnknownField(String),
+
+    #[error("Naming strategy produced an empty name")]
+    Empty,
 }
 
 impl Default for ClusterConfig {
@@ -269,12 +417,26 @@ impl Default for ClusterConfig {
             naming: Option::default(),
             agent_namespace: AGENT_NAMESPACE.to_string().into(),
             host_network: Some(true),
-            #[cfg(feature = "agent-initiated")]
-            agent_initiated: Some(true),
+            agent_initiated: None,
             selectors: Selectors::default(),
+            unimport: None,
+            drain: None,
+            deletion_policy: None,
+            adoption: None,
             patch_resource: Some(true),
             agent_env_vars: None,
             agent_tolerations: None,
+            agent_affinity: None,
+            agent_resources: None,
+            agent_scheduling_customization: None,
+            private_repo_url: None,
+            proxy: None,
+            topology_tolerations: None,
+            pause_during_upgrade: None,
+            upgrade_gate: None,
+            agent_delivery: None,
+            registration_token_ttl: None,
+            scoped_kubeconfig: None,
         }
     }
 }
@@ -462,6 +624,82 @@ impl NamingStrategy {
             None => name,
         })
     }
+
+    /// Render the Fleet cluster name from the template, prefix and suffix,
+    /// truncating it to the `max_length` if set.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the template is malformed or the result is empty.
+    pub fn render(&self, values: &NamingValues) -> NamingResult<String> {
+        let name = match &self.template {
+            Some(template) => dns_safe(&render_template(template, values)?),
+            None => values.name.to_string(),
+        };
+        let name = self.apply(Some(name)).unwrap_or_default();
+
+        let name = match self.max_length {
+            Some(max_length) if name.len() > max_length => truncate_with_hash(&name, max_length),
+            _ => name,
+        };
+
+        if name.is_empty() {
+            return Err(NamingError::Empty);
+        }
+
+        Ok(name)
+    }
+}
+
+fn render_template(template: &str, values: &NamingValues) -> NamingResult<String> {
+    let mut rendered = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let expression = &rest[start + 2..];
+        let end = expression
+            .find("}}")
+            .ok_or_else(|| NamingError::Unclosed(template.to_string()))?;
+        rendered.push_str(match expression[..end].trim() {
+            ".Name" => values.name,
+            ".Namespace" => values.namespace,
+            ".ClusterClass" => values.cluster_class,
+            field => return Err(NamingError::UnknownField(field.to_string())),
+        });
+        rest = &expression[end + 2..];
+    }
+    rendered.push_str(rest);
+
+    Ok(rendered)
+}
+
+/// Lowercase the name and replace characters not allowed in DNS-1123 names with `-`.
+fn dns_safe(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | '0'..='9' | '-' | '.' => c,
+            _ => '-',
+        })
+        .collect::<String>()
+        .trim_matches(|c: char| !c.is_ascii_alphanumeric())
+        .to_string()
+}
+
+/// Truncate the name to `max_length`, replacing the tail with a stable hash of the full name.
+fn truncate_with_hash(name: &str, max_length: usize) -> String {
+    // 32-bit FNV-1a, stable across releases and architectures
+    let hash = name.bytes().fold(0x811c_9dc5_u32, |hash, b| {
+        (hash ^ u32::from(b)).wrapping_mul(0x0100_0193)
+    });
+    let hash = format!("{hash:08x}");
+    let keep = max_length.saturating_sub(hash.len() + 1);
+    let head: String = name.chars().take(keep).collect();
+
+    match head.trim_end_matches(|c: char| !c.is_ascii_alphanumeric()) {
+        "" => hash.chars().take(max_length).collect(),
+        head => format!("{head}-{hash}"),
+    }
 }
 
 /// Selectors is controlling Fleet import strategy settings.
@@ -475,6 +713,182 @@ pub struct Selectors {
     pub selector: LabelSelector,
 }
 
+/// `UpgradeGate` configures the readiness gate for CAPI topology upgrades.
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UpgradeGate {
+    /// Maximum time in seconds an upgrade is held back. Once elapsed, the upgrade proceeds
+    /// and the override is reported as an event on the cluster. Defaults to `3600`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_seconds: Option<i64>,
+
+    /// Interval in seconds before CAPI checks the gate again. Defaults to `30`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_seconds: Option<i32>,
+
+    /// Names of the `ClusterClasses` the gate applies to. If not set, the gate applies to
+    /// every cluster.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cluster_classes: Option<Vec<String>>,
+}
+
+impl UpgradeGate {
+    pub(crate) fn timeout_seconds(&self) -> i64 {
+        self.timeout_seconds.unwrap_or(UPGRADE_GATE_TIMEOUT_SECONDS)
+    }
+
+    pub(crate) fn retry_after_seconds(&self) -> i32 {
+        self.retry_after_seconds
+            .unwrap_or(UPGRADE_GATE_RETRY_AFTER_SECONDS)
+    }
+
+    /// Check if the gate applies to clusters of the `ClusterClass`.
+    pub(crate) fn applies_to(&self, cluster_class: Option<&str>) -> bool {
+        self.cluster_classes.as_ref().is_none_or(|classes| {
+            cluster_class.is_some_and(|class| classes.iter().any(|c| c == class))
+        })
+    }
+}
+
+/// `DrainConfig` configures the drain phase of CAPI cluster deletion.
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DrainConfig {
+    /// Maximum time in seconds the deletion is held while bundle deployments are removed.
+    /// Once elapsed, the deletion proceeds and the timeout is reported as an event on the
+    /// cluster. Defaults to `600`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_seconds: Option<i64>,
+}
+
+impl DrainConfig {
+    pub(crate) fn timeout_seconds(&self) -> i64 {
+        self.timeout_seconds.unwrap_or(DRAIN_TIMEOUT_SECONDS)
+    }
+
+    /// Check if the drain started at `started` ran out of time.
+    pub(crate) fn timed_out(&self, started: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        now - started >= TimeDelta::seconds(self.timeout_seconds())
+    }
+}
+
+/// `ProxyConfig` configures the HTTP proxy used by the fleet agent.
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyConfig {
+    /// Proxy URL for HTTP requests, set as `HTTP_PROXY`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_proxy: Option<String>,
+
+    /// Proxy URL for HTTPS requests, set as `HTTPS_PROXY`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub https_proxy: Option<String>,
+
+    /// Extra hosts, domains and CIDRs excluded from proxying, appended to `NO_PROXY`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_proxy: Option<Vec<String>>,
+}
+
+/// `ScopedKubeconfig` configures the ServiceAccount used by Fleet in the workload cluster.
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScopedKubeconfig {
+    /// Cluster wide RBAC rules granted to the Fleet ServiceAccount. Defaults to the permissions
+    /// required to deploy the fleet agent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rules: Option<Vec<PolicyRule>>,
+
+    /// Lifetime of the ServiceAccount token in seconds. The kubeconfig is rotated once half of
+    /// the lifetime has passed. Defaults to `86400`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_expiration_seconds: Option<i64>,
+}
+
+impl ScopedKubeconfig {
+    pub(crate) fn token_expiration_seconds(&self) -> i64 {
+        self.token_expiration_seconds
+            .unwrap_or(SCOPED_TOKEN_EXPIRATION_SECONDS)
+    }
+}
+
+/// `UnimportConfig` controls what happens to the fleet resources of an imported cluster,
+/// once the cluster or its namespace stop matching the import selectors.
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UnimportConfig {
+    /// Keep the fleet resources with `retain`, or delete them with `remove`.
+    pub policy: UnimportPolicy,
+
+    /// Uninstall the fleet agent from the downstream cluster, when fleet resources are removed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remove_agent: Option<bool>,
+}
+
+/// `AdoptionConfig` controls how pre-existing Fleet clusters are matched to CAPI clusters,
+/// and what happens to them.
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AdoptionConfig {
+    /// Take over the matching Fleet cluster with `adopt`, leave it in place and do not import
+    /// the CAPI cluster with `skip`, or report an error with `fail`.
+    pub policy: AdoptionPolicy,
+
+    /// Label or annotation on the Fleet cluster, with the CAPI cluster name as the value.
+    /// Fleet clusters with the imported cluster name are matched as well.
+    /// Defaults to `adopt.fleet.addons.cluster.x-k8s.io`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+}
+
+impl AdoptionConfig {
+    pub(crate) fn key(&self) -> &str {
+        self.key.as_deref().unwrap_or(ADOPTION_KEY)
+    }
+
+    /// Check if the Fleet cluster is marked for adoption by the CAPI cluster.
+    pub(crate) fn matches(&self, fleet: &impl ResourceExt, cluster_name: &str) -> bool {
+        let key = self.key();
+        fleet
+            .labels()
+            .get(key)
+            .or(fleet.annotations().get(key))
+            .is_some_and(|name| name == cluster_name)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum AdoptionPolicy {
+    Adopt,
+    #[default]
+    Skip,
+    Fail,
+}
+
+/// `DeletionPolicy` selects what happens to the Fleet objects once the CAPI cluster is deleted.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, JsonSchema, Default, PartialEq)]
+pub enum DeletionPolicy {
+    #[default]
+    Delete,
+    Orphan,
+}
+
+/// `AgentDelivery` selects how the fleet agent registration is applied to the workload cluster.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, JsonSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum AgentDelivery {
+    Kubeconfig,
+    ClusterResourceSet,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum UnimportPolicy {
+    #[default]
+    Retain,
+    Remove,
+}
+
 impl FleetAddonConfig {
     // Raw cluster selector
     pub(crate) fn cluster_selector(&self) -> Result<Selector, ParseExpressionError> {
@@ -496,6 +910,65 @@ impl FleetAddonConfig {
             .try_into()
     }
 
+    /// Check if the cluster is in the import scope. A cluster is imported when it matches the
+    /// cluster selector, or when its namespace matches the namespace selector.
+    pub(crate) fn cluster_in_scope(
+        &self,
+        cluster_labels: &BTreeMap<String, String>,
+        ns_labels: &BTreeMap<String, String>,
+    ) -> Result<bool, ParseExpressionError> {
+        Ok(self.cluster_selector()?.matches(cluster_labels)
+            || self.namespace_selector()?.matches(ns_labels))
+    }
+
+    // Check for un-import removal setting. Evaluates to false if fleet resources are retained.
+    pub(crate) fn unimport_remove_enabled(&self) -> bool {
+        self.unimport_config()
+            .is_some_and(|u| u.policy == UnimportPolicy::Remove)
+    }
+
+    // Check for fleet agent removal on un-import.
+    pub(crate) fn unimport_remove_agent_enabled(&self) -> bool {
+        self.unimport_remove_enabled()
+            && self
+                .unimport_config()
+                .and_then(|u| u.remove_agent)
+                .unwrap_or_default()
+    }
+
+    // Agent registration delivery method for agent initiated clusters, if enabled.
+    pub(crate) fn agent_delivery(&self) -> Option<AgentDelivery> {
+        self.spec.cluster.as_ref()?.agent_delivery
+    }
+
+    // Pause Fleet clusters during topology upgrades, if enabled.
+    pub(crate) fn pause_during_upgrade(&self) -> bool {
+        self.spec
+            .cluster
+            .as_ref()
+            .and_then(|c| c.pause_during_upgrade)
+            .is_some_and(|enabled| enabled)
+    }
+
+    // Scoped ServiceAccount kubeconfig settings, if enabled.
+    pub(crate) fn scoped_kubeconfig(&self) -> Option<&ScopedKubeconfig> {
+        self.spec.cluster.as_ref()?.scoped_kubeconfig.as_ref()
+    }
+
+    // Drain settings for the cluster deletion, if enabled.
+    pub(crate) fn drain(&self) -> Option<&DrainConfig> {
+        self.spec.cluster.as_ref()?.drain.as_ref()
+    }
+
+    // Adoption settings for pre-existing Fleet clusters, if enabled.
+    pub(crate) fn adoption(&self) -> Option<&AdoptionConfig> {
+        self.spec.cluster.as_ref()?.adoption.as_ref()
+    }
+
+    fn unimport_config(&self) -> Option<&UnimportConfig> {
+        self.spec.cluster.as_ref()?.unimport.as_ref()
+    }
+
     // Check for general cluster operations, like create, patch, etc. Evaluates to false if disabled.
     pub(crate) fn cluster_operations_enabled(&self) -> bool {
         self.spec.cluster.is_some()
@@ -529,13 +1002,198 @@ impl FleetAddonConfig {
     }
 }
 
+#[derive(Error, Debug, PartialEq)]
+#[error("{}", .0.join("; "))]
+pub struct ConfigValidationError(pub Vec<String>);
+
+impl FleetAddonConfig {
+    /// Validate the settings which can't be expressed in the CRD schema.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigValidationError` listing every invalid setting.
+    pub fn validate(&self) -> Result<(), ConfigValidationError> {
+        let mut errors = vec![];
+
+        if let Err(e) = self.cluster_selector() {
+            errors.push(format!("spec.cluster.selector: {e}"));
+        }
+        if let Err(e) = self.namespace_selector() {
+            errors.push(format!("spec.cluster.namespaceSelector: {e}"));
+        }
+
+        if let Some(naming) = self.spec.cluster.as_ref().and_then(|c| c.naming.as_ref()) {
+            errors.extend(
+                naming
+                    .validate()
+                    .err()
+                    .map(|e| format!("spec.cluster.naming: {e}")),
+            );
+        }
+
+        let server = self.spec.config.as_ref().and_then(|c| c.server.as_ref());
+        if let Some(Server::Custom(options)) = server {
+            if options.api_server_url.is_none() && options.api_server_ca_config_ref.is_none() {
+                errors.push(
+                    "spec.config.server.custom: requires apiServerUrl or apiServerCaConfigRef"
+                        .into(),
+                );
+            }
+            if let Some(url) = options.api_server_url.as_deref().filter(|url| !is_url(url)) {
+                errors.push(format!(
+                    "spec.config.server.custom.apiServerUrl: `{url}` is not a valid URL"
+                ));
+            }
+            if let Some(reference) = &options.api_server_ca_config_ref {
+                errors.extend(reference_errors(
+                    "spec.config.server.custom.apiServerCaConfigRef",
+                    reference,
+                ));
+            }
+        }
+
+        let config_map = self
+            .spec
+            .feature_gates()
+            .and_then(|f| f.config_map.as_ref());
+        if let Some(config_map) = config_map {
+            match &config_map.reference {
+                None => errors.push("spec.config.featureGates.configMap.ref: required".into()),
+                Some(reference) => errors.extend(reference_errors(
+                    "spec.config.featureGates.configMap.ref",
+                    reference,
+                )),
+            }
+            // Feature gates synced to a ConfigMap are reconciled instead of the chart install
+            if config_map.reference.is_some() && self.spec.install.is_some() {
+                errors.push(
+                    "spec.config.featureGates.configMap: can't be combined with spec.install"
+                        .into(),
+                );
+            }
+        }
+
+        if let Some(FleetInstall {
+            install_version: Install::Version(version),
+        }) = &self.spec.install
+        {
+            if !is_version(version) {
+                errors.push(format!(
+                    "spec.install.version: `{version}` is not a valid semantic version"
+                ));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigValidationError(errors))
+        }
+    }
+}
+
+impl NamingStrategy {
+    /// Validate the strategy produces DNS-1123 compliant names.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing the invalid name or template.
+    pub fn validate(&self) -> Result<(), String> {
+        let samples = [
+            NamingValues {
+                name: "cluster",
+                namespace: "default",
+                cluster_class: "class",
+            },
+            NamingValues {
+                name: "cluster",
+                namespace: "default",
+                cluster_class: "",
+            },
+        ];
+
+        for values in &samples {
+            let name = self.render(values).map_err(|e| e.to_string())?;
+            if !is_dns_subdomain(&name) {
+                return Err(format!("produces invalid name `{name}`"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Check the name is a valid DNS-1123 subdomain.
+fn is_dns_subdomain(name: &str) -> bool {
+    name.len() <= 253
+        && name.split('.').all(|part| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+                && !part.starts_with('-')
+                && !part.ends_with('-')
+        })
+}
+
+/// Missing fields of a reference to a namespaced object.
+fn reference_errors(field: &str, reference: &ObjectReference) -> Vec<String> {
+    [
+        ("name", &reference.name),
+        ("namespace", &reference.namespace),
+    ]
+    .into_iter()
+    .filter(|(_, value)| value.as_deref().is_none_or(str::is_empty))
+    .map(|(key, _)| format!("{field}.{key}: required"))
+    .collect()
+}
+
+/// Check the URL has an `http` or `https` scheme and a host.
+fn is_url(url: &str) -> bool {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return false;
+    };
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+
+    matches!(scheme, "http" | "https")
+        && !host.is_empty()
+        && !url.chars().any(|c| c.is_whitespace() || c.is_control())
+}
+
+/// Check the version follows the `[v]MAJOR.MINOR.PATCH[-PRERELEASE][+BUILD]` format.
+fn is_version(version: &str) -> bool {
+    let version = version.strip_prefix('v').unwrap_or(version);
+    let (version, _build) = version.split_once('+').unwrap_or((version, ""));
+    let (core, pre_release) = match version.split_once('-') {
+        Some((core, pre_release)) => (core, Some(pre_release)),
+        None => (version, None),
+    };
+
+    let core_valid = core.split('.').count() == 3
+        && core
+            .split('.')
+            .all(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+    let pre_release_valid = pre_release.is_none_or(|pre| {
+        pre.split('.')
+            .all(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+    });
+
+    core_valid && pre_release_valid
+}
+
 #[cfg(test)]
 mod tests {
-    use std::str::FromStr;
+    use std::{collections::BTreeMap, str::FromStr};
 
     use crate::api::fleet_addon_config::{
-        FeatureGates, FleetChartValues, FleetSettingsSpec, NamingStrategy,
+        AdoptionConfig, ClusterConfig, FeatureGates, FeaturesConfigMap, FleetAddonConfig,
+        FleetChartValues, FleetConfig, FleetInstall, FleetSettingsSpec, Install, InstallOptions,
+        NamingError, NamingStrategy, NamingValues, Server, UnimportConfig, UnimportPolicy,
     };
+    use crate::api::fleet_cluster;
+    use k8s_openapi::api::core::v1::ObjectReference;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::{LabelSelector, LabelSelectorRequirement};
+    use kube::api::ObjectMeta;
 
     #[tokio::test]
     async fn test_naming_strategy() {
@@ -544,6 +1202,7 @@ mod tests {
             NamingStrategy {
                 prefix: "prefix".to_string().into(),
                 suffix: "suffix".to_string().into(),
+                ..Default::default()
             }
             .apply("test".to_string().into())
         );
@@ -579,11 +1238,223 @@ mod tests {
             NamingStrategy {
                 prefix: "prefix".to_string().into(),
                 suffix: "suffix".to_string().into(),
+                ..Default::default()
             }
             .apply(None)
         );
     }
 
+    #[tokio::test]
+    async fn test_naming_template() {
+        let values = NamingValues {
+            name: "cluster",
+            namespace: "Team_A",
+            cluster_class: "quick-start",
+        };
+
+        assert_eq!(
+            Ok("team-a-cluster-quick-start".to_string()),
+            NamingStrategy {
+                template: Some("{{ .Namespace }}-{{ .Name }}-{{.ClusterClass}}".into()),
+                ..Default::default()
+            }
+            .render(&values)
+        );
+
+        assert_eq!(
+            Ok("capi-team-a-cluster".to_string()),
+            NamingStrategy {
+                prefix: "capi-".to_string().into(),
+                template: Some("{{ .Namespace }}-{{ .Name }}-{{ .ClusterClass }}".into()),
+                ..Default::default()
+            }
+            .render(&NamingValues {
+                cluster_class: "",
+                ..values.clone()
+            })
+        );
+
+        assert_eq!(
+            Err(NamingError::UnknownField(".Labels".into())),
+            NamingStrategy {
+                template: Some("{{ .Labels }}".into()),
+                ..Default::default()
+            }
+            .render(&values)
+        );
+
+        assert_eq!(
+            Err(NamingError::Unclosed("{{ .Name".into())),
+            NamingStrategy {
+                template: Some("{{ .Name".into()),
+                ..Default::default()
+            }
+            .render(&values)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_naming_max_length() {
+        let strategy = NamingStrategy {
+            template: Some("{{ .Namespace }}-{{ .Name }}".into()),
+            max_length: Some(20),
+            ..Default::default()
+        };
+        let values = NamingValues {
+            name: "a-very-long-cluster-name",
+            namespace: "default",
+            ..Default::default()
+        };
+
+        let name = strategy.render(&values).unwrap();
+        assert_eq!(20, name.len());
+        assert!(name.starts_with("default-a-"));
+        // Hash is stable between renders
+        assert_eq!(name, strategy.render(&values).unwrap());
+        // Different source names produce different results
+        assert_ne!(
+            name,
+            strategy
+                .render(&NamingValues {
+                    namespace: "other",
+                    ..values.clone()
+                })
+                .unwrap()
+        );
+
+        assert_eq!(
+            Ok("short".to_string()),
+            NamingStrategy {
+                max_length: Some(20),
+                ..Default::default()
+            }
+            .render(&NamingValues {
+                name: "short",
+                ..Default::default()
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validate_config() {
+        assert!(FleetAddonConfig::default().validate().is_ok());
+
+        let mut config = FleetAddonConfig::default();
+        let cluster = config.spec.cluster.get_or_insert_default();
+        cluster.selectors.selector = LabelSelector {
+            match_expressions: Some(vec![LabelSelectorRequirement {
+                key: "import".into(),
+                operator: "Unknown".into(),
+                values: None,
+            }]),
+            ..Default::default()
+        };
+        cluster.naming = Some(NamingStrategy {
+            prefix: Some("Capi_".into()),
+            ..Default::default()
+        });
+        config.spec.config = Some(FleetConfig {
+            server: Some(Server::Custom(InstallOptions {
+                api_server_url: Some("fleet.example.com".into()),
+                api_server_ca_config_ref: None,
+            })),
+            feature_gates: Some(FeatureGates {
+                config_map: Some(FeaturesConfigMap::default()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        config.spec.install = Some(FleetInstall {
+            install_version: Install::Version("latest".into()),
+        });
+
+        let errors = config.validate().unwrap_err().0;
+        assert_eq!(5, errors.len(), "{errors:?}");
+
+        // An empty custom server, an incomplete config map reference, and the feature gates
+        // config map combined with the chart install
+        config.spec.cluster = Some(ClusterConfig::default());
+        config.spec.install = Some(FleetInstall {
+            install_version: Install::FollowLatest(true),
+        });
+        config.spec.config = Some(FleetConfig {
+            server: Some(Server::Custom(InstallOptions::default())),
+            feature_gates: Some(FeatureGates {
+                config_map: Some(FeaturesConfigMap {
+                    reference: Some(ObjectReference {
+                        name: Some("fleet-controller".into()),
+                        ..Default::default()
+                    }),
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        let errors = config.validate().unwrap_err().0;
+        assert_eq!(3, errors.len(), "{errors:?}");
+
+        // Either custom server setting is enough, and plain http URLs are accepted
+        let reference = ObjectReference {
+            name: Some("fleet-ca".into()),
+            namespace: Some("default".into()),
+            ..Default::default()
+        };
+        config.spec.install = None;
+        for (url, ca) in [
+            (None, Some(reference.clone())),
+            (Some("http://fleet.example.com"), None),
+            (Some("https://10.0.0.1:6443"), Some(reference.clone())),
+        ] {
+            config.spec.config = Some(FleetConfig {
+                server: Some(Server::Custom(InstallOptions {
+                    api_server_url: url.map(Into::into),
+                    api_server_ca_config_ref: ca,
+                })),
+                feature_gates: Some(FeatureGates {
+                    config_map: Some(FeaturesConfigMap {
+                        reference: Some(reference.clone()),
+                    }),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            });
+            assert!(config.validate().is_ok(), "{url:?}");
+        }
+
+        config.spec.cluster = Some(ClusterConfig::default());
+        config.spec.config = None;
+        config.spec.install = Some(FleetInstall {
+            install_version: Install::Version("v0.12.0-rc.1".into()),
+        });
+        assert!(config.validate().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_cluster_in_scope() {
+        let import = BTreeMap::from([("import".to_string(), "true".to_string())]);
+        let empty = BTreeMap::default();
+
+        let mut config = FleetAddonConfig::default();
+        assert!(config.cluster_in_scope(&empty, &empty).unwrap());
+        assert!(!config.unimport_remove_enabled());
+
+        let cluster = config.spec.cluster.get_or_insert_default();
+        cluster.selectors.selector = LabelSelector {
+            match_labels: Some(import.clone()),
+            ..Default::default()
+        };
+        cluster.selectors.namespace_selector = cluster.selectors.selector.clone();
+        cluster.unimport = Some(UnimportConfig {
+            policy: UnimportPolicy::Remove,
+            remove_agent: None,
+        });
+        assert!(config.cluster_in_scope(&import, &empty).unwrap());
+        assert!(config.cluster_in_scope(&empty, &import).unwrap());
+        assert!(!config.cluster_in_scope(&empty, &empty).unwrap());
+        assert!(config.unimport_remove_enabled());
+        assert!(!config.unimport_remove_agent_enabled());
+    }
+
     #[tokio::test]
     async fn test_sync_config_map() {
         let want_fleet_data = r"extraEnv:
@@ -634,4 +1505,51 @@ foo:
 
         assert_eq!(want_fleet_data.to_string(), data.fleet.to_string());
     }
+
+    #[test]
+    fn test_adoption_matches() {
+        let fleet =
+            |labels: &[(&str, &str)], annotations: &[(&str, &str)]| fleet_cluster::Cluster {
+                metadata: ObjectMeta {
+                    labels: Some(
+                        labels
+                            .iter()
+                            .map(|(k, v)| (k.to_string(), v.to_string()))
+                            .collect(),
+                    ),
+                    annotations: Some(
+                        annotations
+                            .iter()
+                            .map(|(k, v)| (k.to_string(), v.to_string()))
+                            .collect(),
+                    ),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+
+        let adoption = AdoptionConfig::default();
+        assert!(adoption.matches(
+            &fleet(&[("adopt.fleet.addons.cluster.x-k8s.io", "test")], &[]),
+            "test"
+        ));
+        assert!(adoption.matches(
+            &fleet(&[], &[("adopt.fleet.addons.cluster.x-k8s.io", "test")]),
+            "test"
+        ));
+        assert!(!adoption.matches(
+            &fleet(&[("adopt.fleet.addons.cluster.x-k8s.io", "other")], &[]),
+            "test"
+        ));
+
+        let adoption = AdoptionConfig {
+            key: Some("example.com/cluster".into()),
+            ..Default::default()
+        };
+        assert!(adoption.matches(&fleet(&[("example.com/cluster", "test")], &[]), "test"));
+        assert!(!adoption.matches(
+            &fleet(&[("adopt.fleet.addons.cluster.x-k8s.io", "test")], &[]),
+            "test"
+        ));
+    }
 }
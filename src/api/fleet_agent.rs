@@ -0,0 +1,363 @@
+use std::collections::BTreeMap;
+
+use k8s_openapi::{
+    ByteString,
+    api::{
+        apps::v1::{StatefulSet, StatefulSetSpec},
+        core::v1::{
+            Affinity, ConfigMap, Container, EnvVar, EnvVarSource, Namespace, ObjectFieldSelector,
+            PodSpec, PodTemplateSpec, ResourceRequirements, Secret, ServiceAccount, Toleration,
+        },
+        rbac::v1::{ClusterRoleBinding, RoleRef, Subject},
+    },
+    apimachinery::pkg::apis::meta::v1::LabelSelector,
+};
+use kube::{
+    Resource, ResourceExt as _,
+    api::{ApiResource, DynamicObject, GroupVersionKind, ObjectMeta},
+};
+use serde::Serialize;
+use serde_json::json;
+use thiserror::Error;
+
+use super::{
+    capi_cluster::{CLUSTER_NAME_LABEL, Cluster},
+    capi_contract::CapiVersion,
+};
+
+pub static AGENT_BOOTSTRAP_SECRET: &str = "fleet-agent-bootstrap";
+pub static AGENT_NAME: &str = "fleet-agent";
+pub static RESOURCE_SET_GROUP: &str = "addons.cluster.x-k8s.io";
+pub static RESOURCE_SET_SECRET_TYPE: &str = "addons.cluster.x-k8s.io/resource-set";
+
+/// `AgentRegistration` describes the fleet agent installation in a downstream cluster.
+/// The agent registers with the Fleet controller using the values of the bootstrap secret.
+#[derive(Clone, Debug, Default)]
+pub struct AgentRegistration {
+    pub namespace: String,
+    pub image: String,
+    pub client_id: Option<String>,
+    pub host_network: Option<bool>,
+    pub tolerations: Vec<Toleration>,
+    /// Additional agent environment variables, such as the proxy settings.
+    pub env: Vec<EnvVar>,
+    pub affinity: Option<Affinity>,
+    pub resources: Option<ResourceRequirements>,
+    /// Bootstrap secret values: registration token, API server URL and CA, and namespaces.
+    pub bootstrap: BTreeMap<String, String>,
+}
+
+#[derive(Error, Debug)]
+pub enum AgentManifestError {
+    #[error("Agent manifest encode error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Agent manifest YAML encode error: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+}
+
+pub type AgentManifestResult<T, E = AgentManifestError> = std::result::Result<T, E>;
+
+impl AgentRegistration {
+    /// Agent manifests with their API resources, in the order they are applied.
+    pub fn manifests(&self) -> AgentManifestResult<Vec<(ApiResource, DynamicObject)>> {
+        let meta = |name: &str| ObjectMeta {
+            name: Some(name.into()),
+            namespace: Some(self.namespace.clone()),
+            ..Default::default()
+        };
+        let labels = BTreeMap::from([("app".to_string(), AGENT_NAME.to_string())]);
+
+        let namespace = Namespace {
+            metadata: ObjectMeta {
+                name: Some(self.namespace.clone()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let service_account = ServiceAccount {
+            metadata: meta(AGENT_NAME),
+            ..Default::default()
+        };
+
+        let binding = ClusterRoleBinding {
+            metadata: ObjectMeta {
+                name: Some(format!("{AGENT_NAME}-{}", self.namespace)),
+                ..Default::default()
+            },
+            role_ref: RoleRef {
+                api_group: "rbac.authorization.k8s.io".into(),
+                kind: "ClusterRole".into(),
+                name: "cluster-admin".into(),
+            },
+            subjects: Some(vec![Subject {
+                kind: "ServiceAccount".into(),
+                name: AGENT_NAME.into(),
+                namespace: Some(self.namespace.clone()),
+                ..Default::default()
+            }]),
+        };
+
+        let bootstrap = Secret {
+            metadata: meta(AGENT_BOOTSTRAP_SECRET),
+            data: Some(
+                self.bootstrap
+                    .iter()
+                    .map(|(k, v)| (k.clone(), ByteString(v.clone().into_bytes())))
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+
+        let config = ConfigMap {
+            metadata: meta(AGENT_NAME),
+            data: Some(BTreeMap::from([(
+                "config".to_string(),
+                json!({"clientID": self.client_id.clone().unwrap_or_default()}).to_string(),
+            )])),
+            ..Default::default()
+        };
+
+        let mut env = vec![
+            EnvVar {
+                name: "NAMESPACE".into(),
+                value_from: Some(EnvVarSource {
+                    field_ref: Some(ObjectFieldSelector {
+                        field_path: "metadata.namespace".into(),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            EnvVar {
+                name: "GENERATION".into(),
+                value: Some("bundle".into()),
+                ..Default::default()
+            },
+        ];
+        env.extend(self.env.iter().cloned());
+
+        let agent = StatefulSet {
+            metadata: meta(AGENT_NAME),
+            spec: Some(StatefulSetSpec {
+                service_name: Some(AGENT_NAME.into()),
+                selector: LabelSelector {
+                    match_labels: Some(labels.clone()),
+                    ..Default::default()
+                },
+                template: PodTemplateSpec {
+                    metadata: Some(ObjectMeta {
+                        labels: Some(labels),
+                        ..Default::default()
+                    }),
+                    spec: Some(PodSpec {
+                        service_account_name: Some(AGENT_NAME.into()),
+                        host_network: self.host_network,
+                        tolerations: Some(self.tolerations.clone()),
+                        containers: vec![Container {
+                            name: AGENT_NAME.into(),
+                            image: Some(self.image.clone()),
+                            command: Some(vec!["fleetagent".into()]),
+                            env: Some(env),
+                            resources: self.resources.clone(),
+                            ..Default::default()
+                        }],
+                        affinity: self.affinity.clone(),
+                        ..Default::default()
+                    }),
+                },
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        Ok(vec![
+            dynamic(&namespace)?,
+            dynamic(&service_account)?,
+            dynamic(&binding)?,
+            dynamic(&bootstrap)?,
+            dynamic(&config)?,
+            dynamic(&agent)?,
+        ])
+    }
+
+    /// Agent manifests as a multi-document YAML.
+    pub fn to_yaml(&self) -> AgentManifestResult<String> {
+        let documents = self
+            .manifests()?
+            .iter()
+            .map(|(_, manifest)| serde_yaml::to_string(manifest))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(documents.join("---\n"))
+    }
+
+    /// Secret and CAPI `ClusterResourceSet` applying the agent manifests to the cluster,
+    /// selected by the cluster name label.
+    pub fn to_resource_set(
+        &self,
+        cluster: &Cluster,
+        capi_version: CapiVersion,
+    ) -> AgentManifestResult<(Secret, DynamicObject)> {
+        let name = resource_set_name(cluster);
+        let metadata = ObjectMeta {
+            name: Some(name.clone()),
+            namespace: cluster.namespace(),
+            owner_references: cluster.owner_ref(&()).map(|owner| vec![owner]),
+            ..Default::default()
+        };
+
+        let secret = Secret {
+            metadata: metadata.clone(),
+            type_: Some(RESOURCE_SET_SECRET_TYPE.into()),
+            data: Some(BTreeMap::from([(
+                format!("{AGENT_NAME}.yaml"),
+                ByteString(self.to_yaml()?.into_bytes()),
+            )])),
+            ..Default::default()
+        };
+
+        let mut resource_set = DynamicObject::new(&name, &resource_set_resource(capi_version));
+        resource_set.metadata = metadata;
+        resource_set.data = json!({
+            "spec": {
+                "clusterSelector": {
+                    "matchLabels": {
+                        CLUSTER_NAME_LABEL: cluster.name_any(),
+                    }
+                },
+                "resources": [{"kind": "Secret", "name": name}],
+                "strategy": "Reconcile",
+            }
+        });
+
+        Ok((secret, resource_set))
+    }
+}
+
+/// Name of the `ClusterResourceSet` and secret delivering the agent to the cluster.
+pub fn resource_set_name(cluster: &Cluster) -> String {
+    format!("{}-{AGENT_NAME}", cluster.name_any())
+}
+
+/// CAPI `ClusterResourceSet` API resource, in the served Cluster API version.
+pub fn resource_set_resource(capi_version: CapiVersion) -> ApiResource {
+    ApiResource::from_gvk_with_plural(
+        &GroupVersionKind::gvk(
+            RESOURCE_SET_GROUP,
+            capi_version.as_str(),
+            "ClusterResourceSet",
+        ),
+        "clusterresourcesets",
+    )
+}
+
+fn dynamic<K>(resource: &K) -> AgentManifestResult<(ApiResource, DynamicObject)>
+where
+    K: Resource<DynamicType = ()> + Serialize,
+{
+    Ok((
+        ApiResource::erase::<K>(&()),
+        serde_json::from_value(serde_json::to_value(resource)?)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use k8s_openapi::{
+        api::core::v1::{Affinity, EnvVar, ResourceRequirements},
+        apimachinery::pkg::api::resource::Quantity,
+    };
+    use serde_json::json;
+
+    use crate::api::{
+        capi_cluster::{CLUSTER_NAME_LABEL, Cluster},
+        capi_contract::CapiVersion,
+    };
+
+    use super::{AGENT_BOOTSTRAP_SECRET, AgentRegistration, RESOURCE_SET_SECRET_TYPE};
+
+    #[test]
+    fn test_agent_manifests() {
+        let registration = AgentRegistration {
+            namespace: "fleet-addon-agent".into(),
+            image: "rancher/fleet-agent:v0.12.0".into(),
+            client_id: Some("client".into()),
+            env: vec![EnvVar {
+                name: "HTTPS_PROXY".into(),
+                value: Some("http://proxy:3128".into()),
+                ..Default::default()
+            }],
+            resources: Some(ResourceRequirements {
+                limits: Some(BTreeMap::from([(
+                    "memory".to_string(),
+                    Quantity("512Mi".into()),
+                )])),
+                ..Default::default()
+            }),
+            affinity: Some(Affinity::default()),
+            bootstrap: BTreeMap::from([("token".to_string(), "secret-token".to_string())]),
+            ..Default::default()
+        };
+
+        let manifests = registration.manifests().unwrap();
+        let kinds: Vec<_> = manifests.iter().map(|(ar, _)| ar.kind.as_str()).collect();
+        assert_eq!(
+            kinds,
+            [
+                "Namespace",
+                "ServiceAccount",
+                "ClusterRoleBinding",
+                "Secret",
+                "ConfigMap",
+                "StatefulSet"
+            ]
+        );
+
+        let (_, bootstrap) = &manifests[3];
+        assert_eq!(
+            bootstrap.metadata.name.as_deref(),
+            Some(AGENT_BOOTSTRAP_SECRET)
+        );
+        assert_eq!(bootstrap.data["data"]["token"], "c2VjcmV0LXRva2Vu");
+        assert_eq!(
+            bootstrap.metadata.namespace.as_deref(),
+            Some("fleet-addon-agent")
+        );
+
+        let (_, agent) = &manifests[5];
+        let pod = &agent.data["spec"]["template"]["spec"];
+        assert_eq!(pod["affinity"], json!({}));
+        assert_eq!(pod["containers"][0]["env"][2]["name"], "HTTPS_PROXY");
+        assert_eq!(
+            pod["containers"][0]["resources"]["limits"]["memory"],
+            "512Mi"
+        );
+
+        let cluster: Cluster = serde_json::from_value(json!({
+            "metadata": {"name": "test", "namespace": "default", "uid": "1234"},
+        }))
+        .unwrap();
+        let (secret, resource_set) = registration
+            .to_resource_set(&cluster, CapiVersion::V1Beta2)
+            .unwrap();
+        assert_eq!(secret.type_.as_deref(), Some(RESOURCE_SET_SECRET_TYPE));
+        assert_eq!(
+            resource_set.types.unwrap().api_version,
+            "addons.cluster.x-k8s.io/v1beta2"
+        );
+        assert_eq!(
+            resource_set.metadata.name.as_deref(),
+            Some("test-fleet-agent")
+        );
+        assert_eq!(
+            resource_set.data["spec"]["clusterSelector"]["matchLabels"][CLUSTER_NAME_LABEL],
+            "test"
+        );
+        assert_eq!(registration.to_yaml().unwrap().matches("---\n").count(), 5);
+    }
+}
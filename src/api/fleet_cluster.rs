@@ -5,8 +5,22 @@ use kube::{
 };
 use serde::{Deserialize, Serialize};
 
+pub static BUNDLE_DEPLOYMENT_CLUSTER_LABEL: &str = "fleet.cattle.io/cluster";
+pub static BUNDLE_DEPLOYMENT_CLUSTER_NAMESPACE_LABEL: &str = "fleet.cattle.io/cluster-namespace";
+pub static KUBECONFIG_HASH_ANNOTATION: &str = "kubeconfig-hash.fleet.addons.cluster.x-k8s.io";
+
+/// Algorithm prefix of the kubeconfig hash. Hashes recorded with a different algorithm are
+/// replaced without redeploying the agent.
+static KUBECONFIG_HASH_PREFIX: &str = "fnv1a64-";
+
+use crate::api::capi_cluster::{
+    CLUSTER_NAME_LABEL, ClusterCondition, FLEET_AGENT_READY_CONDITION,
+    FLEET_BUNDLES_READY_CONDITION, REDEPLOY_AGENT_ANNOTATION,
+};
 use crate::api::comparable::ResourceDiff;
-use std::collections::HashSet;
+use crate::api::fleet_bundle_deployment::BundleDeployment;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
 
 #[derive(Resource, Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 #[resource(inherit = fleet_api_rs::fleet_cluster::Cluster)]
@@ -44,8 +58,16 @@ impl ResourceDiff for Cluster {
         let spec_equal = template_values_equal
             && self.spec.agent_namespace == other.spec.agent_namespace
             && self.spec.host_network == other.spec.host_network
+            && self.spec.kube_config_secret == other.spec.kube_config_secret
+            && self.spec.client_id == other.spec.client_id
             && self.spec.agent_env_vars == other.spec.agent_env_vars
-            && self.spec.agent_tolerations == other.spec.agent_tolerations;
+            && self.spec.agent_tolerations == other.spec.agent_tolerations
+            && self.spec.agent_affinity == other.spec.agent_affinity
+            && self.spec.agent_resources == other.spec.agent_resources
+            && self.spec.agent_scheduling_customization
+                == other.spec.agent_scheduling_customization
+            && self.spec.private_repo_url == other.spec.private_repo_url
+            && self.spec.paused.unwrap_or_default() == other.spec.paused.unwrap_or_default();
 
         if !spec_equal {
             return true;
@@ -74,3 +96,341 @@ impl ResourceDiff for Cluster {
         !annotations_equal || !labels_equal || !owner_references_equal
     }
 }
+
+impl Cluster {
+    fn status_field(&self, pointer: &str) -> Option<String> {
+        let status = serde_json::to_value(self.status.as_ref()?).ok()?;
+        status
+            .pointer(pointer)
+            .and_then(Value::as_str)
+            .filter(|v| !v.is_empty())
+            .map(Into::into)
+    }
+
+    /// Record the hash of the kubeconfig secret data, to detect kubeconfig rotations.
+    pub(crate) fn set_kubeconfig_hash(&mut self, kubeconfig: &[u8]) {
+        // 64-bit FNV-1a, stable across releases and architectures
+        let hash = kubeconfig
+            .iter()
+            .fold(0xcbf2_9ce4_8422_2325_u64, |hash, &b| {
+                (hash ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3)
+            });
+        self.annotations_mut().insert(
+            KUBECONFIG_HASH_ANNOTATION.to_string(),
+            format!("{KUBECONFIG_HASH_PREFIX}{hash:016x}"),
+        );
+    }
+
+    /// Compare the kubeconfig hash and redeploy annotations with the existing Fleet cluster.
+    /// Returns the changed annotations, and the reason to redeploy the agent, if any.
+    pub(crate) fn agent_redeploy<'a>(
+        &'a self,
+        existing: &Self,
+    ) -> (BTreeMap<&'static str, &'a String>, Option<&'static str>) {
+        let changed: BTreeMap<&str, &String> =
+            [KUBECONFIG_HASH_ANNOTATION, REDEPLOY_AGENT_ANNOTATION]
+                .into_iter()
+                .filter_map(|key| Some((key, self.annotations().get(key)?)))
+                .filter(|(key, value)| existing.annotations().get(*key) != Some(*value))
+                .collect();
+
+        // The first recorded kubeconfig hash is not a rotation
+        let rotated = changed.contains_key(KUBECONFIG_HASH_ANNOTATION)
+            && existing
+                .annotations()
+                .get(KUBECONFIG_HASH_ANNOTATION)
+                .is_some_and(|hash| hash.starts_with(KUBECONFIG_HASH_PREFIX));
+        let reason = if rotated {
+            Some("KubeconfigRotated")
+        } else if changed.contains_key(REDEPLOY_AGENT_ANNOTATION) {
+            Some("RedeployRequested")
+        } else {
+            None
+        };
+
+        (changed, reason)
+    }
+
+    /// Labels removed on drain, so bundles targeting the cluster by labels or through cluster
+    /// groups no longer match it. The origin cluster label is kept to map the Fleet cluster.
+    pub(crate) fn drain_labels(&self) -> BTreeMap<&String, Option<String>> {
+        self.labels()
+            .keys()
+            .filter(|label| *label != CLUSTER_NAME_LABEL)
+            .map(|label| (label, None))
+            .collect()
+    }
+
+    /// Check if the agent has checked in with the Fleet controller.
+    pub(crate) fn agent_registered(&self) -> bool {
+        self.status_field("/agent/lastSeen").is_some()
+    }
+
+    /// Agent readiness, reported once the agent has checked in with the Fleet controller.
+    pub(crate) fn agent_ready_condition(&self) -> ClusterCondition {
+        if self.agent_registered() {
+            return ClusterCondition::ready(FLEET_AGENT_READY_CONDITION, "AgentConnected");
+        }
+
+        ClusterCondition::not_ready(
+            FLEET_AGENT_READY_CONDITION,
+            "Info",
+            "AgentNotConnected",
+            format!(
+                "Waiting for the fleet agent of `{}` to connect",
+                self.name_any()
+            ),
+        )
+    }
+}
+
+/// `BundleSummary` aggregates the state of the `BundleDeployments` targeting a Fleet cluster.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BundleSummary {
+    pub ready: i64,
+    pub modified: i64,
+    pub not_ready: i64,
+    pub error: i64,
+}
+
+impl BundleSummary {
+    pub const STATES: [&'static str; 4] = ["ready", "modified", "not_ready", "error"];
+
+    #[must_use]
+    pub fn from_deployments(deployments: &[BundleDeployment]) -> Self {
+        deployments
+            .iter()
+            .fold(Self::default(), |mut summary, deployment| {
+                match Self::state(deployment) {
+                    "ready" => summary.ready += 1,
+                    "modified" => summary.modified += 1,
+                    "error" => summary.error += 1,
+                    _ => summary.not_ready += 1,
+                }
+                summary
+            })
+    }
+
+    fn state(deployment: &BundleDeployment) -> &'static str {
+        let Some(status) = deployment.status.as_ref() else {
+            return "not_ready";
+        };
+
+        let errored = status.non_ready_status.iter().flatten().any(|s| {
+            s.summary
+                .as_ref()
+                .and_then(|summary| summary.error)
+                .unwrap_or_default()
+        });
+        let display_state = status.display.as_ref().and_then(|d| d.state.as_deref());
+        match display_state {
+            _ if errored => "error",
+            Some("ErrApplied") => "error",
+            Some("Modified") => "modified",
+            Some("Ready") => "ready",
+            Some(_) => "not_ready",
+            None if status.non_modified == Some(false) => "modified",
+            None if status.ready == Some(true) => "ready",
+            None => "not_ready",
+        }
+    }
+
+    #[must_use]
+    pub fn total(&self) -> i64 {
+        self.ready + self.modified + self.not_ready + self.error
+    }
+
+    /// Counts by state, in the `STATES` order.
+    #[must_use]
+    pub fn counts(&self) -> [i64; 4] {
+        [self.ready, self.modified, self.not_ready, self.error]
+    }
+
+    /// Bundle readiness, reported once all deployments targeting the cluster are ready.
+    #[must_use]
+    pub fn condition(&self) -> ClusterCondition {
+        if self.ready == self.total() {
+            return ClusterCondition::ready(FLEET_BUNDLES_READY_CONDITION, "BundlesReady");
+        }
+
+        let message = format!(
+            "{}/{} bundle deployments ready, {} modified, {} not ready, {} errored",
+            self.ready,
+            self.total(),
+            self.modified,
+            self.not_ready,
+            self.error
+        );
+        if self.error > 0 {
+            ClusterCondition::not_ready(
+                FLEET_BUNDLES_READY_CONDITION,
+                "Error",
+                "BundlesErrored",
+                message,
+            )
+        } else if self.not_ready > 0 {
+            ClusterCondition::not_ready(
+                FLEET_BUNDLES_READY_CONDITION,
+                "Warning",
+                "BundlesNotReady",
+                message,
+            )
+        } else {
+            ClusterCondition::not_ready(
+                FLEET_BUNDLES_READY_CONDITION,
+                "Warning",
+                "BundlesModified",
+                message,
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kube::ResourceExt as _;
+    use serde_json::json;
+
+    use crate::api::{comparable::ResourceDiff as _, fleet_bundle_deployment::BundleDeployment};
+
+    use crate::api::capi_cluster::{CLUSTER_NAME_LABEL, REDEPLOY_AGENT_ANNOTATION};
+
+    use super::{BundleSummary, Cluster, KUBECONFIG_HASH_ANNOTATION};
+
+    fn deployment(status: serde_json::Value) -> BundleDeployment {
+        serde_json::from_value(json!({
+            "apiVersion": "fleet.cattle.io/v1alpha1",
+            "kind": "BundleDeployment",
+            "metadata": {"name": "test", "namespace": "cluster-default-test"},
+            "spec": {},
+            "status": status,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_bundle_summary() {
+        let summary = BundleSummary::from_deployments(&[
+            deployment(json!({"display": {"state": "Ready"}, "ready": true, "nonModified": true})),
+            deployment(json!({"ready": true, "nonModified": true})),
+            deployment(json!({"display": {"state": "Modified"}, "nonModified": false})),
+            deployment(json!({"display": {"state": "WaitApplied"}})),
+            deployment(json!({
+                "display": {"state": "NotReady"},
+                "nonReadyStatus": [{"kind": "Deployment", "summary": {"error": true}}],
+            })),
+        ]);
+
+        assert_eq!(
+            summary,
+            BundleSummary {
+                ready: 2,
+                modified: 1,
+                not_ready: 1,
+                error: 1,
+            }
+        );
+        assert_eq!(summary.total(), 5);
+        assert_eq!(
+            summary.condition().reason.as_deref(),
+            Some("BundlesErrored")
+        );
+        assert_eq!(BundleSummary::default().condition().status, "True");
+    }
+
+    #[test]
+    fn test_agent_redeploy() {
+        let mut existing = Cluster::default();
+        let mut fleet = Cluster::default();
+        fleet.set_kubeconfig_hash(b"kubeconfig");
+        assert_eq!(
+            fleet.annotations().get(KUBECONFIG_HASH_ANNOTATION).unwrap(),
+            "fnv1a64-92fea4efa5551726"
+        );
+
+        // The first recorded hash is not a rotation
+        let (changed, reason) = fleet.agent_redeploy(&existing);
+        assert!(changed.contains_key(KUBECONFIG_HASH_ANNOTATION));
+        assert_eq!(reason, None);
+
+        // A hash recorded with a different algorithm is replaced without a redeploy
+        existing
+            .annotations_mut()
+            .insert(KUBECONFIG_HASH_ANNOTATION.into(), "1f2e3d4c5b6a7988".into());
+        let (changed, reason) = fleet.agent_redeploy(&existing);
+        assert!(changed.contains_key(KUBECONFIG_HASH_ANNOTATION));
+        assert_eq!(reason, None);
+
+        existing.metadata.annotations = fleet.metadata.annotations.clone();
+        let (changed, reason) = fleet.agent_redeploy(&existing);
+        assert!(changed.is_empty());
+        assert_eq!(reason, None);
+
+        fleet.set_kubeconfig_hash(b"rotated");
+        let (changed, reason) = fleet.agent_redeploy(&existing);
+        assert_eq!(changed.len(), 1);
+        assert_eq!(reason, Some("KubeconfigRotated"));
+
+        fleet.metadata.annotations = existing.metadata.annotations.clone();
+        fleet
+            .annotations_mut()
+            .insert(REDEPLOY_AGENT_ANNOTATION.into(), "1".into());
+        let (changed, reason) = fleet.agent_redeploy(&existing);
+        assert_eq!(
+            changed.keys().copied().collect::<Vec<_>>(),
+            [REDEPLOY_AGENT_ANNOTATION]
+        );
+        assert_eq!(reason, Some("RedeployRequested"));
+    }
+
+    #[test]
+    fn test_drain_labels() {
+        let mut fleet = Cluster::default();
+        assert!(fleet.drain_labels().is_empty());
+
+        fleet.labels_mut().extend([
+            (CLUSTER_NAME_LABEL.to_string(), "test".to_string()),
+            ("env".to_string(), "prod".to_string()),
+            (
+                "clusterclass-name.fleet.addons.cluster.x-k8s.io".to_string(),
+                "quick-start".to_string(),
+            ),
+        ]);
+        let labels: Vec<&str> = fleet
+            .drain_labels()
+            .into_iter()
+            .map(|(label, value)| {
+                assert!(value.is_none());
+                label.as_str()
+            })
+            .collect();
+        assert_eq!(
+            labels,
+            ["clusterclass-name.fleet.addons.cluster.x-k8s.io", "env"]
+        );
+    }
+
+    #[test]
+    fn test_cluster_diff() {
+        let existing: Cluster = serde_json::from_value(json!({
+            "metadata": {"name": "test", "namespace": "default"},
+            "spec": {},
+            "status": {},
+        }))
+        .unwrap();
+        assert!(!existing.diff(&existing));
+
+        // Agent settings changes are patched on the existing Fleet cluster
+        for (field, value) in [
+            ("agentAffinity", json!({"nodeAffinity": {}})),
+            ("agentResources", json!({"limits": {"memory": "512Mi"}})),
+            ("agentSchedulingCustomization", json!({})),
+            ("privateRepoURL", json!("registry.example.com")),
+        ] {
+            let mut desired = serde_json::to_value(&existing).unwrap();
+            desired["spec"][field] = value;
+            let desired: Cluster = serde_json::from_value(desired).unwrap();
+            assert!(desired.diff(&existing), "{field} change is not patched");
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use fleet_api_rs::fleet_cluster_registration_token::{
     ClusterRegistrationTokenSpec, ClusterRegistrationTokenStatus,
 };
@@ -6,6 +7,7 @@ use kube::{
     api::{ObjectMeta, TypeMeta},
 };
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::api::comparable::ResourceDiff;
 
@@ -24,3 +26,52 @@ impl ResourceDiff for ClusterRegistrationToken {
         self.spec != other.spec
     }
 }
+
+impl ClusterRegistrationToken {
+    /// Name of the secret holding the agent registration values, issued by Fleet.
+    pub(crate) fn secret_name(&self) -> Option<String> {
+        let status = serde_json::to_value(self.status.as_ref()?).ok()?;
+        status
+            .pointer("/secretName")
+            .and_then(Value::as_str)
+            .filter(|name| !name.is_empty())
+            .map(Into::into)
+    }
+
+    /// Token expiration time, reported by Fleet once the token is processed.
+    pub(crate) fn expires(&self) -> Option<DateTime<Utc>> {
+        let status = serde_json::to_value(self.status.as_ref()?).ok()?;
+        let expires = status.pointer("/expires").and_then(Value::as_str)?;
+        DateTime::parse_from_rfc3339(expires)
+            .ok()
+            .map(|expires| expires.to_utc())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::ClusterRegistrationToken;
+
+    #[test]
+    fn test_token_expires() {
+        let mut token: ClusterRegistrationToken = serde_json::from_value(json!({
+            "metadata": {"name": "test", "namespace": "default"},
+            "spec": {"ttl": "1h"},
+        }))
+        .unwrap();
+        assert!(token.expires().is_none());
+
+        token = serde_json::from_value(json!({
+            "metadata": {"name": "test", "namespace": "default"},
+            "spec": {"ttl": "1h"},
+            "status": {"expires": "2025-01-01T10:00:00Z", "secretName": "test-token"},
+        }))
+        .unwrap();
+        assert_eq!(
+            token.expires().map(|expires| expires.to_rfc3339()),
+            Some("2025-01-01T10:00:00+00:00".into())
+        );
+    }
+}
@@ -1,9 +1,14 @@
 pub mod bundle_namespace_mapping;
 pub mod capi_cluster;
 pub mod capi_clusterclass;
+pub mod capi_contract;
 pub mod comparable;
+pub mod fleet_addon_cluster_policy;
 pub mod fleet_addon_config;
+pub mod fleet_agent;
+#[rustfmt::skip]
+pub mod fleet_bundle_deployment;
 pub mod fleet_cluster;
-#[cfg(feature = "agent-initiated")]
 pub mod fleet_cluster_registration_token;
 pub mod fleet_clustergroup;
+pub mod scoped_kubeconfig;
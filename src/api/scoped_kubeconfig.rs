@@ -0,0 +1,285 @@
+use k8s_openapi::api::{
+    core::v1::{Namespace, ServiceAccount},
+    rbac::v1::{ClusterRole, ClusterRoleBinding, PolicyRule, Role, RoleBinding, RoleRef, Subject},
+};
+use kube::{api::ObjectMeta, config::Kubeconfig};
+use serde_json::json;
+
+pub static SCOPED_SERVICE_ACCOUNT: &str = "fleet-addon-manager";
+pub static SCOPED_NAMESPACE: &str = "kube-system";
+pub static TOKEN_EXPIRATION_ANNOTATION: &str = "token-expiration.fleet.addons.cluster.x-k8s.io";
+pub static TOKEN_ISSUED_ANNOTATION: &str = "token-issued.fleet.addons.cluster.x-k8s.io";
+
+fn rule(api_group: &str, resources: &[&str], verbs: &[&str]) -> PolicyRule {
+    PolicyRule {
+        api_groups: Some(vec![api_group.into()]),
+        resources: Some(resources.iter().map(|&r| r.into()).collect()),
+        verbs: verbs.iter().map(|&v| v.into()).collect(),
+        ..Default::default()
+    }
+}
+
+/// Name of the `ClusterRole` Fleet binds to the fleet agent.
+#[must_use]
+pub fn agent_cluster_role(agent_namespace: &str) -> String {
+    format!("{agent_namespace}-fleet-agent-role")
+}
+
+/// Cluster wide permissions required by Fleet to deploy the fleet agent into the workload
+/// cluster. RBAC permissions are limited to binding the agent `ClusterRole`, without
+/// `escalate`, so the ServiceAccount can't grant itself more permissions.
+#[must_use]
+pub fn default_rules(agent_namespace: &str) -> Vec<PolicyRule> {
+    vec![
+        rule(
+            "",
+            &["namespaces"],
+            &[
+                "get", "list", "watch", "create", "update", "patch", "delete",
+            ],
+        ),
+        rule(
+            "rbac.authorization.k8s.io",
+            &["clusterroles"],
+            &["get", "list", "watch"],
+        ),
+        rule(
+            "rbac.authorization.k8s.io",
+            &["clusterrolebindings"],
+            &[
+                "get", "list", "watch", "create", "update", "patch", "delete",
+            ],
+        ),
+        PolicyRule {
+            resource_names: Some(vec![agent_cluster_role(agent_namespace)]),
+            ..rule("rbac.authorization.k8s.io", &["clusterroles"], &["bind"])
+        },
+    ]
+}
+
+/// Permissions required by Fleet in the agent namespace.
+#[must_use]
+pub fn namespace_rules() -> Vec<PolicyRule> {
+    vec![
+        rule(
+            "",
+            &["serviceaccounts", "secrets", "configmaps", "services"],
+            &["*"],
+        ),
+        rule("apps", &["deployments", "statefulsets"], &["*"]),
+        rule("networking.k8s.io", &["networkpolicies"], &["*"]),
+    ]
+}
+
+/// RBAC objects bootstrapped with the admin kubeconfig for the Fleet ServiceAccount.
+pub struct ScopedRbac {
+    pub service_account: ServiceAccount,
+    pub cluster_role: ClusterRole,
+    pub cluster_role_binding: ClusterRoleBinding,
+    pub namespace: Namespace,
+    pub role: Role,
+    pub role_binding: RoleBinding,
+
+    /// The fleet agent `ClusterRole` is created upfront, as Fleet can only bind it.
+    pub agent_role: ClusterRole,
+}
+
+/// ServiceAccount with the cluster wide rules, and a `Role` restricted to the agent namespace.
+#[must_use]
+pub fn service_account_rbac(rules: Vec<PolicyRule>, agent_namespace: &str) -> ScopedRbac {
+    let meta = ObjectMeta {
+        name: Some(SCOPED_SERVICE_ACCOUNT.into()),
+        ..Default::default()
+    };
+    let namespaced = ObjectMeta {
+        namespace: Some(agent_namespace.into()),
+        ..meta.clone()
+    };
+    let role_ref = |kind: &str| RoleRef {
+        api_group: "rbac.authorization.k8s.io".into(),
+        kind: kind.into(),
+        name: SCOPED_SERVICE_ACCOUNT.into(),
+    };
+    let subjects = Some(vec![Subject {
+        kind: "ServiceAccount".into(),
+        name: SCOPED_SERVICE_ACCOUNT.into(),
+        namespace: Some(SCOPED_NAMESPACE.into()),
+        ..Default::default()
+    }]);
+
+    ScopedRbac {
+        service_account: ServiceAccount {
+            metadata: ObjectMeta {
+                namespace: Some(SCOPED_NAMESPACE.into()),
+                ..meta.clone()
+            },
+            ..Default::default()
+        },
+        cluster_role: ClusterRole {
+            metadata: meta.clone(),
+            rules: Some(rules),
+            ..Default::default()
+        },
+        cluster_role_binding: ClusterRoleBinding {
+            metadata: meta,
+            role_ref: role_ref("ClusterRole"),
+            subjects: subjects.clone(),
+        },
+        namespace: Namespace {
+            metadata: ObjectMeta {
+                name: Some(agent_namespace.into()),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        role: Role {
+            metadata: namespaced.clone(),
+            rules: Some(namespace_rules()),
+        },
+        role_binding: RoleBinding {
+            metadata: namespaced,
+            role_ref: role_ref("Role"),
+            subjects,
+        },
+        agent_role: ClusterRole {
+            metadata: ObjectMeta {
+                name: Some(agent_cluster_role(agent_namespace)),
+                ..Default::default()
+            },
+            rules: Some(vec![rule("*", &["*"], &["*"])]),
+            ..Default::default()
+        },
+    }
+}
+
+/// Render a kubeconfig authenticating with the ServiceAccount token, against the cluster
+/// of the admin kubeconfig current context.
+pub fn scoped_kubeconfig(admin: &Kubeconfig, token: &str) -> serde_yaml::Result<Option<String>> {
+    let cluster_name = admin
+        .current_context
+        .as_ref()
+        .and_then(|current| admin.contexts.iter().find(|c| &c.name == current))
+        .and_then(|context| context.context.as_ref())
+        .map(|context| context.cluster.clone());
+    let Some(cluster) = admin
+        .clusters
+        .iter()
+        .find(|c| Some(&c.name) == cluster_name.as_ref())
+        .or(admin.clusters.first())
+    else {
+        return Ok(None);
+    };
+
+    serde_yaml::to_string(&json!({
+        "apiVersion": "v1",
+        "kind": "Config",
+        "clusters": [cluster],
+        "users": [{
+            "name": SCOPED_SERVICE_ACCOUNT,
+            "user": {"token": token},
+        }],
+        "contexts": [{
+            "name": SCOPED_SERVICE_ACCOUNT,
+            "context": {"cluster": cluster.name, "user": SCOPED_SERVICE_ACCOUNT},
+        }],
+        "current-context": SCOPED_SERVICE_ACCOUNT,
+    }))
+    .map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use kube::config::Kubeconfig;
+
+    use super::{SCOPED_SERVICE_ACCOUNT, default_rules, scoped_kubeconfig, service_account_rbac};
+
+    #[test]
+    fn test_scoped_kubeconfig() {
+        let admin = Kubeconfig::from_yaml(
+            r"
+apiVersion: v1
+kind: Config
+clusters:
+- name: other
+  cluster:
+    server: https://other:6443
+- name: test
+  cluster:
+    server: https://test:6443
+    certificate-authority-data: Y2E=
+users:
+- name: test-admin
+  user:
+    client-certificate-data: Y2VydA==
+    client-key-data: a2V5
+contexts:
+- name: test-admin@test
+  context:
+    cluster: test
+    user: test-admin
+current-context: test-admin@test
+",
+        )
+        .unwrap();
+
+        let kubeconfig = scoped_kubeconfig(&admin, "sa-token").unwrap().unwrap();
+        let scoped = Kubeconfig::from_yaml(&kubeconfig).unwrap();
+        assert_eq!(scoped.clusters.len(), 1);
+        let cluster = scoped.clusters[0].cluster.as_ref().unwrap();
+        assert_eq!(cluster.server.as_deref(), Some("https://test:6443"));
+        assert_eq!(cluster.certificate_authority_data.as_deref(), Some("Y2E="));
+        assert_eq!(scoped.auth_infos[0].name, SCOPED_SERVICE_ACCOUNT);
+        assert!(!kubeconfig.contains("client-key-data"));
+        assert!(kubeconfig.contains("sa-token"));
+
+        assert!(
+            scoped_kubeconfig(&Kubeconfig::default(), "sa-token")
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_service_account_rbac() {
+        let rbac =
+            service_account_rbac(default_rules("cattle-fleet-system"), "cattle-fleet-system");
+
+        // RBAC resources are limited to binding the agent role, without escalation
+        let rules = rbac.cluster_role.rules.unwrap();
+        assert!(rules.iter().all(|rule| {
+            !rule.verbs.iter().any(|v| v == "*" || v == "escalate")
+                && rule
+                    .resources
+                    .iter()
+                    .flatten()
+                    .all(|r| r != "secrets" && r != "roles")
+        }));
+        let bind = rules.iter().find(|rule| rule.verbs == ["bind"]).unwrap();
+        assert_eq!(
+            bind.resource_names,
+            Some(vec!["cattle-fleet-system-fleet-agent-role".to_string()])
+        );
+        assert_eq!(
+            rbac.agent_role.metadata.name.as_deref(),
+            Some("cattle-fleet-system-fleet-agent-role")
+        );
+
+        // Secrets are only granted in the agent namespace
+        assert_eq!(
+            rbac.role.metadata.namespace.as_deref(),
+            Some("cattle-fleet-system")
+        );
+        assert!(
+            rbac.role.rules.unwrap().iter().any(|rule| rule
+                .resources
+                .iter()
+                .flatten()
+                .any(|r| r == "secrets"))
+        );
+        assert_eq!(rbac.role_binding.role_ref.kind, "Role");
+        assert_eq!(
+            rbac.namespace.metadata.name.as_deref(),
+            Some("cattle-fleet-system")
+        );
+    }
+}
@@ -0,0 +1,628 @@
+use std::fmt::Debug;
+
+use k8s_openapi::{NamespaceResourceScope, api::core::v1::Secret};
+use kube::{
+    Api, Client, Resource, ResourceExt as _,
+    api::{ListParams, ObjectMeta, PostParams, TypeMeta},
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use thiserror::Error;
+use tracing::info;
+
+use crate::{
+    api::{
+        bundle_namespace_mapping::BundleNamespaceMapping,
+        capi_cluster::{CLUSTER_NAME_LABEL, Cluster},
+        fleet_cluster,
+        fleet_cluster_registration_token::ClusterRegistrationToken,
+        fleet_clustergroup::ClusterGroup,
+    },
+    controllers::controller::{FLEET_FINALIZER, remove_owner},
+};
+
+pub static ARCHIVE_API_VERSION: &str = "addons.cluster.x-k8s.io/v1alpha1";
+pub static ARCHIVE_KIND: &str = "FleetArchive";
+
+pub type ArchiveResult<T, E = ArchiveError> = std::result::Result<T, E>;
+
+#[derive(Error, Debug)]
+pub enum ArchiveError {
+    #[error("Kube error: {0}")]
+    Kube(#[from] kube::Error),
+
+    #[error("Cluster `{0}` is not paused, pause it before the export")]
+    NotPaused(String),
+
+    #[error("Unsupported archive `{0}`, expected `{ARCHIVE_API_VERSION}` `{ARCHIVE_KIND}`")]
+    Unsupported(String),
+}
+
+/// `FleetArchive` holds the Fleet objects of CAPI clusters, stripped of the metadata bound
+/// to the source management cluster.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FleetArchive {
+    #[serde(flatten)]
+    pub types: TypeMeta,
+
+    #[serde(default)]
+    pub clusters: Vec<fleet_cluster::Cluster>,
+
+    #[serde(default)]
+    pub cluster_groups: Vec<ClusterGroup>,
+
+    #[serde(default)]
+    pub bundle_namespace_mappings: Vec<BundleNamespaceMapping>,
+
+    /// Registration tokens of agent initiated clusters. The token secrets are issued again
+    /// by Fleet on the target management cluster.
+    #[serde(default)]
+    pub cluster_registration_tokens: Vec<ClusterRegistrationToken>,
+
+    /// Scoped kubeconfig secrets referenced by the Fleet clusters. The CAPI kubeconfig
+    /// secrets are moved by `clusterctl move`.
+    #[serde(default)]
+    pub secrets: Vec<Secret>,
+}
+
+impl Default for FleetArchive {
+    fn default() -> Self {
+        Self {
+            types: TypeMeta {
+                api_version: ARCHIVE_API_VERSION.to_string(),
+                kind: ARCHIVE_KIND.to_string(),
+            },
+            clusters: vec![],
+            cluster_groups: vec![],
+            bundle_namespace_mappings: vec![],
+            cluster_registration_tokens: vec![],
+            secrets: vec![],
+        }
+    }
+}
+
+impl FleetArchive {
+    /// Archive the Fleet objects of the cluster. The objects on the source are left untouched.
+    async fn export_cluster(&mut self, client: Client, cluster: &Cluster) -> ArchiveResult<()> {
+        let namespace = cluster.namespace().unwrap_or_default();
+        let uid = cluster.uid().unwrap_or_default();
+        let capi_kubeconfig = format!("{}-kubeconfig", cluster.name_any());
+
+        let fleet_clusters = Api::<fleet_cluster::Cluster>::namespaced(client.clone(), &namespace);
+        let secrets = Api::<Secret>::namespaced(client.clone(), &namespace);
+        let selector = format!("{CLUSTER_NAME_LABEL}={}", cluster.name_any());
+        for fleet in fleet_clusters
+            .list(&ListParams::default().labels(&selector))
+            .await?
+        {
+            let scoped_secret = fleet
+                .spec
+                .kube_config_secret
+                .as_ref()
+                .filter(|name| **name != capi_kubeconfig);
+            if let Some(secret_name) = scoped_secret {
+                if let Some(secret) = secrets.get_opt(secret_name).await? {
+                    push_unique(
+                        &mut self.secrets,
+                        Secret {
+                            metadata: portable(&secret.metadata),
+                            data: secret.data,
+                            type_: secret.type_,
+                            ..Default::default()
+                        },
+                    );
+                }
+            }
+
+            push_unique(
+                &mut self.clusters,
+                fleet_cluster::Cluster {
+                    types: Some(TypeMeta::resource::<fleet_cluster::Cluster>()),
+                    metadata: portable(&fleet.metadata),
+                    spec: fleet.spec,
+                    status: None,
+                },
+            );
+        }
+
+        // Cluster groups created for the cluster ClusterClass in the cluster namespace
+        let groups = Api::<ClusterGroup>::namespaced(client.clone(), &namespace);
+        for group in groups.list(&ListParams::default()).await? {
+            if group
+                .owner_references()
+                .iter()
+                .all(|owner| owner.uid != uid)
+            {
+                continue;
+            }
+
+            push_unique(
+                &mut self.cluster_groups,
+                ClusterGroup {
+                    types: Some(TypeMeta::resource::<ClusterGroup>()),
+                    metadata: portable(&group.metadata),
+                    spec: group.spec,
+                    status: None,
+                },
+            );
+        }
+
+        let tokens = Api::<ClusterRegistrationToken>::namespaced(client.clone(), &namespace);
+        if let Some(token) = tokens.get_opt(&cluster.name_any()).await? {
+            if token
+                .owner_references()
+                .iter()
+                .any(|owner| owner.uid == uid)
+            {
+                push_unique(
+                    &mut self.cluster_registration_tokens,
+                    ClusterRegistrationToken {
+                        types: Some(TypeMeta::resource::<ClusterRegistrationToken>()),
+                        metadata: portable(&token.metadata),
+                        spec: token.spec,
+                        status: None,
+                    },
+                );
+            }
+        }
+
+        let class_namespace = cluster
+            .cluster_class_namespace()
+            .filter(|class_namespace| *class_namespace != namespace);
+        if let Some(class_namespace) = class_namespace {
+            let mappings = Api::<BundleNamespaceMapping>::namespaced(client, class_namespace);
+            if let Some(mapping) = mappings.get_opt(&namespace).await? {
+                push_unique(
+                    &mut self.bundle_namespace_mappings,
+                    BundleNamespaceMapping {
+                        types: Some(TypeMeta::resource::<BundleNamespaceMapping>()),
+                        metadata: portable(&mapping.metadata),
+                        ..mapping
+                    },
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Remove the owner references to the cluster from its archived objects on the source.
+    async fn release_cluster(&self, client: Client, cluster: &Cluster) -> ArchiveResult<()> {
+        let namespace = cluster.namespace().unwrap_or_default();
+        let uid = cluster.uid().unwrap_or_default();
+
+        release_objects(client.clone(), &self.clusters, &namespace, &uid).await?;
+        release_objects(client.clone(), &self.cluster_groups, &namespace, &uid).await?;
+        release_objects(client.clone(), &self.secrets, &namespace, &uid).await?;
+        release_objects(client, &self.cluster_registration_tokens, &namespace, &uid).await
+    }
+}
+
+/// Imported CAPI clusters in the namespace, or in all namespaces, which have to be paused.
+async fn imported_clusters(client: Client, namespace: Option<&str>) -> ArchiveResult<Vec<Cluster>> {
+    let api: Api<Cluster> = match namespace {
+        Some(namespace) => Api::namespaced(client, namespace),
+        None => Api::all(client),
+    };
+
+    let imported: Vec<Cluster> = api
+        .list(&ListParams::default())
+        .await?
+        .items
+        .into_iter()
+        .filter(|cluster| cluster.finalizers().iter().any(|f| f == FLEET_FINALIZER))
+        .collect();
+
+    if let Some(cluster) = imported.iter().find(|cluster| !cluster.paused()) {
+        return Err(ArchiveError::NotPaused(format!(
+            "{}/{}",
+            cluster.namespace().unwrap_or_default(),
+            cluster.name_any()
+        )));
+    }
+
+    Ok(imported)
+}
+
+/// Export the Fleet objects of the imported CAPI clusters in the namespace, or in all
+/// namespaces. The clusters have to be paused, so the controller leaves their Fleet objects
+/// in place. The objects on the source are not modified, see [`release`].
+///
+/// # Errors
+///
+/// This function will return an error if a cluster is not paused, or if the Fleet objects
+/// cannot be fetched.
+pub async fn export(client: Client, namespace: Option<&str>) -> ArchiveResult<FleetArchive> {
+    let mut archive = FleetArchive::default();
+    for cluster in &imported_clusters(client.clone(), namespace).await? {
+        archive.export_cluster(client.clone(), cluster).await?;
+        info!(
+            "Exported fleet objects of cluster {}/{}",
+            cluster.namespace().unwrap_or_default(),
+            cluster.name_any()
+        );
+    }
+
+    Ok(archive)
+}
+
+/// Remove the owner references to the CAPI clusters from the archived Fleet objects on the
+/// source, so they are not garbage collected once `clusterctl move` deletes the clusters.
+/// Called once the archive is stored.
+///
+/// # Errors
+///
+/// This function will return an error if a cluster is not paused, or if the Fleet objects
+/// cannot be updated.
+pub async fn release(
+    client: Client,
+    namespace: Option<&str>,
+    archive: &FleetArchive,
+) -> ArchiveResult<()> {
+    for cluster in &imported_clusters(client.clone(), namespace).await? {
+        archive.release_cluster(client.clone(), cluster).await?;
+        info!(
+            "Released fleet objects of cluster {}/{}",
+            cluster.namespace().unwrap_or_default(),
+            cluster.name_any()
+        );
+    }
+
+    Ok(())
+}
+
+/// Restore the archived Fleet objects on the target management cluster, ahead of
+/// `clusterctl move`. Existing objects are left untouched. Fleet clusters keep the origin
+/// cluster label, so the moved CAPI clusters re-parent them.
+///
+/// # Errors
+///
+/// This function will return an error if the archive is not supported, or if the Fleet
+/// objects cannot be created.
+pub async fn restore(client: Client, archive: FleetArchive) -> ArchiveResult<()> {
+    if archive.types.api_version != ARCHIVE_API_VERSION || archive.types.kind != ARCHIVE_KIND {
+        return Err(ArchiveError::Unsupported(format!(
+            "{} {}",
+            archive.types.api_version, archive.types.kind
+        )));
+    }
+
+    for secret in &archive.secrets {
+        create_opt(client.clone(), secret).await?;
+    }
+
+    for mapping in &archive.bundle_namespace_mappings {
+        create_opt(client.clone(), mapping).await?;
+    }
+
+    for group in &archive.cluster_groups {
+        create_opt(client.clone(), group).await?;
+    }
+
+    for token in &archive.cluster_registration_tokens {
+        create_opt(client.clone(), token).await?;
+    }
+
+    for fleet in &archive.clusters {
+        create_opt(client.clone(), fleet).await?;
+    }
+
+    Ok(())
+}
+
+/// Create the object, skipping objects which already exist.
+async fn create_opt<K>(client: Client, object: &K) -> ArchiveResult<()>
+where
+    K: Resource<DynamicType = (), Scope = NamespaceResourceScope>,
+    K: Clone + Serialize + DeserializeOwned + Debug,
+{
+    let namespace = object.namespace().unwrap_or_default();
+    let api = Api::<K>::namespaced(client, &namespace);
+    match api.create(&PostParams::default(), object).await {
+        Ok(_) => info!(
+            "Restored {} {namespace}/{}",
+            K::kind(&()),
+            object.name_any()
+        ),
+        Err(kube::Error::Api(e)) if e.code == 409 => info!(
+            "{} {namespace}/{} already exists, skipping",
+            K::kind(&()),
+            object.name_any()
+        ),
+        Err(e) => return Err(e.into()),
+    }
+
+    Ok(())
+}
+
+async fn release_objects<K>(
+    client: Client,
+    objects: &[K],
+    namespace: &str,
+    uid: &str,
+) -> ArchiveResult<()>
+where
+    K: Resource<DynamicType = (), Scope = NamespaceResourceScope>,
+    K: Clone + DeserializeOwned + Debug,
+{
+    let api = Api::<K>::namespaced(client, namespace);
+    for object in objects
+        .iter()
+        .filter(|object| object.namespace().as_deref() == Some(namespace))
+    {
+        remove_owner(&api, &object.name_any(), uid).await?;
+    }
+
+    Ok(())
+}
+
+/// Object metadata without the fields bound to the source management cluster.
+fn portable(meta: &ObjectMeta) -> ObjectMeta {
+    ObjectMeta {
+        name: meta.name.clone(),
+        namespace: meta.namespace.clone(),
+        labels: meta.labels.clone(),
+        annotations: meta.annotations.clone(),
+        ..Default::default()
+    }
+}
+
+fn push_unique<K: Resource>(objects: &mut Vec<K>, object: K) {
+    if !objects.iter().any(|o| {
+        o.meta().name == object.meta().name && o.meta().namespace == object.meta().namespace
+    }) {
+        objects.push(object);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use http::{Method, Request, Response, StatusCode};
+    use kube::{Client, api::ObjectMeta, client::Body};
+    use serde_json::{Value, json};
+    use tokio::task::JoinHandle;
+
+    use crate::api::fleet_cluster;
+
+    use super::{ARCHIVE_KIND, FleetArchive, export, portable, restore};
+
+    /// Serve the requests with the responder, returning the served method and path pairs.
+    fn mock_client(
+        respond: impl Fn(&Method, &str) -> (StatusCode, Value) + Send + 'static,
+    ) -> (Client, JoinHandle<Vec<(Method, String)>>) {
+        let (service, mut handle) = tower_test::mock::pair::<Request<Body>, Response<Body>>();
+        let server = tokio::spawn(async move {
+            let mut served = vec![];
+            while let Some((request, send)) = handle.next_request().await {
+                let path = request.uri().path().to_string();
+                let (status, body) = respond(request.method(), &path);
+                send.send_response(
+                    Response::builder()
+                        .status(status)
+                        .body(Body::from(serde_json::to_vec(&body).unwrap()))
+                        .unwrap(),
+                );
+                served.push((request.method().clone(), path));
+            }
+            served
+        });
+
+        (Client::new(service, "default"), server)
+    }
+
+    fn status(code: u16, reason: &str) -> Value {
+        json!({
+            "apiVersion": "v1",
+            "kind": "Status",
+            "status": "Failure",
+            "reason": reason,
+            "message": reason,
+            "code": code,
+        })
+    }
+
+    fn owner() -> Value {
+        json!([{
+            "apiVersion": "cluster.x-k8s.io/v1beta1",
+            "kind": "Cluster",
+            "name": "test",
+            "uid": "5678",
+        }])
+    }
+
+    #[test]
+    fn test_archive() {
+        let fleet: fleet_cluster::Cluster = serde_json::from_value(json!({
+            "apiVersion": "fleet.cattle.io/v1alpha1",
+            "kind": "Cluster",
+            "metadata": {
+                "name": "test",
+                "namespace": "default",
+                "uid": "1234",
+                "resourceVersion": "42",
+                "labels": {"cluster-name.fleet.addons.cluster.x-k8s.io": "test"},
+                "ownerReferences": [{
+                    "apiVersion": "cluster.x-k8s.io/v1beta1",
+                    "kind": "Cluster",
+                    "name": "test",
+                    "uid": "5678",
+                }],
+                "finalizers": ["fleet.cattle.io/cluster-finalizer"],
+            },
+            "spec": {"clientID": "stable-id"},
+        }))
+        .unwrap();
+
+        let metadata = portable(&fleet.metadata);
+        assert_eq!(
+            metadata,
+            ObjectMeta {
+                name: Some("test".into()),
+                namespace: Some("default".into()),
+                labels: Some(BTreeMap::from([(
+                    "cluster-name.fleet.addons.cluster.x-k8s.io".into(),
+                    "test".into()
+                )])),
+                ..Default::default()
+            }
+        );
+
+        let archive = FleetArchive {
+            clusters: vec![fleet_cluster::Cluster {
+                metadata,
+                status: None,
+                ..fleet
+            }],
+            ..Default::default()
+        };
+        let data = serde_yaml::to_string(&archive).unwrap();
+        assert!(data.contains(&format!("kind: {ARCHIVE_KIND}")));
+        assert_eq!(
+            serde_yaml::from_str::<FleetArchive>(&data).unwrap(),
+            archive
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export() {
+        let (client, server) = mock_client(|method, path| {
+            assert_eq!(method, Method::GET, "{path}");
+            let list = |kind: &str, items: Value| json!({"apiVersion": "v1", "kind": kind, "metadata": {}, "items": items});
+            match path {
+                p if p.starts_with("/apis/cluster.x-k8s.io/") => (
+                    StatusCode::OK,
+                    list(
+                        "ClusterList",
+                        json!([{
+                            "apiVersion": "cluster.x-k8s.io/v1beta1",
+                            "kind": "Cluster",
+                            "metadata": {
+                                "name": "test",
+                                "namespace": "default",
+                                "uid": "5678",
+                                "finalizers": ["fleet.addons.cluster.x-k8s.io"],
+                            },
+                            "spec": {"paused": true},
+                        }]),
+                    ),
+                ),
+                "/apis/fleet.cattle.io/v1alpha1/namespaces/default/clusters" => (
+                    StatusCode::OK,
+                    list(
+                        "ClusterList",
+                        json!([{
+                            "apiVersion": "fleet.cattle.io/v1alpha1",
+                            "kind": "Cluster",
+                            "metadata": {
+                                "name": "test",
+                                "namespace": "default",
+                                "ownerReferences": owner(),
+                            },
+                            "spec": {"clientID": "stable-id", "kubeConfigSecret": "test-kubeconfig"},
+                        }]),
+                    ),
+                ),
+                "/apis/fleet.cattle.io/v1alpha1/namespaces/default/clustergroups" => {
+                    (StatusCode::OK, list("ClusterGroupList", json!([])))
+                }
+                "/apis/fleet.cattle.io/v1alpha1/namespaces/default/clusterregistrationtokens/test" => {
+                    (
+                        StatusCode::OK,
+                        json!({
+                            "apiVersion": "fleet.cattle.io/v1alpha1",
+                            "kind": "ClusterRegistrationToken",
+                            "metadata": {
+                                "name": "test",
+                                "namespace": "default",
+                                "ownerReferences": owner(),
+                            },
+                            "spec": {"ttl": "1h"},
+                            "status": {"secretName": "test-token"},
+                        }),
+                    )
+                }
+                _ => (StatusCode::NOT_FOUND, status(404, "NotFound")),
+            }
+        });
+
+        let archive = export(client, Some("default")).await.unwrap();
+        let served = server.await.unwrap();
+
+        // The source objects are only read
+        assert!(served.iter().all(|(method, _)| method == Method::GET));
+        assert_eq!(archive.clusters.len(), 1);
+        assert!(archive.clusters[0].metadata.owner_references.is_none());
+        assert_eq!(
+            archive.clusters[0].spec.client_id.as_deref(),
+            Some("stable-id")
+        );
+        assert_eq!(archive.cluster_registration_tokens.len(), 1);
+        assert!(archive.cluster_registration_tokens[0].status.is_none());
+        assert!(archive.secrets.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_restore() {
+        let (client, server) = mock_client(|method, path| {
+            assert_eq!(method, Method::POST, "{path}");
+            match path {
+                "/apis/fleet.cattle.io/v1alpha1/namespaces/default/clusters" => {
+                    (StatusCode::CONFLICT, status(409, "AlreadyExists"))
+                }
+                _ => (
+                    StatusCode::CREATED,
+                    json!({
+                        "apiVersion": "fleet.cattle.io/v1alpha1",
+                        "kind": "ClusterRegistrationToken",
+                        "metadata": {"name": "test", "namespace": "default"},
+                        "spec": {},
+                    }),
+                ),
+            }
+        });
+
+        let object = |kind: &str| {
+            json!({
+                "apiVersion": "fleet.cattle.io/v1alpha1",
+                "kind": kind,
+                "metadata": {"name": "test", "namespace": "default"},
+                "spec": {},
+            })
+        };
+        let archive = FleetArchive {
+            clusters: vec![serde_json::from_value(object("Cluster")).unwrap()],
+            cluster_registration_tokens: vec![
+                serde_json::from_value(object("ClusterRegistrationToken")).unwrap(),
+            ],
+            ..Default::default()
+        };
+        restore(client, archive).await.unwrap();
+
+        // Existing Fleet clusters are skipped
+        let served: Vec<String> = server
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(_, path)| path)
+            .collect();
+        assert_eq!(
+            served,
+            [
+                "/apis/fleet.cattle.io/v1alpha1/namespaces/default/clusterregistrationtokens",
+                "/apis/fleet.cattle.io/v1alpha1/namespaces/default/clusters",
+            ]
+        );
+
+        let unsupported = FleetArchive {
+            types: kube::api::TypeMeta {
+                api_version: "v1".into(),
+                kind: "List".into(),
+            },
+            ..Default::default()
+        };
+        let (client, _) = mock_client(|_, path| panic!("unexpected request {path}"));
+        assert!(restore(client, unsupported).await.is_err());
+    }
+}
@@ -1,24 +1,31 @@
 use crate::api::bundle_namespace_mapping::BundleNamespaceMapping;
-use crate::api::capi_cluster::Cluster;
+use crate::api::capi_cluster::{CAPI_CLUSTER_NAME_LABEL, CLUSTER_NAME_LABEL, Cluster};
 use crate::api::capi_clusterclass::ClusterClass;
+use crate::api::capi_contract::CapiVersion;
+use crate::api::fleet_addon_cluster_policy::FleetAddonClusterPolicy;
 use crate::api::fleet_addon_config::FleetAddonConfig;
-use crate::api::fleet_cluster;
+use crate::api::fleet_bundle_deployment::BundleDeployment;
+use crate::api::fleet_cluster::{
+    self, BUNDLE_DEPLOYMENT_CLUSTER_LABEL, BUNDLE_DEPLOYMENT_CLUSTER_NAMESPACE_LABEL,
+};
 use crate::api::fleet_clustergroup::ClusterGroup;
 use crate::controllers::addon_config::FleetConfig;
 use crate::controllers::controller::{Context, DynamicStream, FleetController, fetch_config};
 use crate::metrics::Diagnostics;
 use crate::multi_dispatcher::{BroadcastStream, MultiDispatcher, broadcaster};
+use crate::webhook::hooks::HookTrigger;
 use crate::{Error, Metrics};
 
 use chrono::Local;
 use clap::Parser;
 use futures::{Stream, StreamExt};
 
+use k8s_openapi::api::core::v1::{Namespace, Secret};
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::{Condition, Time};
-use kube::api::{Patch, PatchParams};
+use kube::api::{PartialObjectMeta, Patch, PatchParams};
 use kube::core::DeserializeGuard;
 use kube::runtime::reflector::ObjectRef;
-use kube::runtime::reflector::store::Writer;
+use kube::runtime::reflector::{Store, store::Writer};
 use kube::runtime::{WatchStreamExt, controller, metadata_watcher, predicates, reflector, watcher};
 use kube::{Resource, ResourceExt};
 use kube::{
@@ -60,6 +67,15 @@ pub struct State {
 
     // Controller readiness barrier
     pub barrier: Arc<Barrier>,
+
+    // Reconcile requests from the Runtime Extension lifecycle hooks
+    hooks: HookTrigger,
+
+    // Cluster policies, shared by the reconcilers and the lifecycle hooks
+    policies: Store<FleetAddonClusterPolicy>,
+
+    // Cluster API version discovered at startup
+    pub capi_version: CapiVersion,
 }
 
 #[derive(Parser, Debug, Clone, Default)]
@@ -67,6 +83,61 @@ pub struct Flags {
     /// helm install allows to select container for performing fleet chart installation
     #[arg(long)]
     pub helm_install: bool,
+
+    /// Enable lease based leader election, so only one replica runs the reconcilers
+    #[arg(long)]
+    pub leader_elect: bool,
+
+    /// Namespace of the leader election lease. Defaults to the controller namespace
+    #[arg(long)]
+    pub leader_election_namespace: Option<String>,
+
+    /// Name of the leader election lease
+    #[arg(long, default_value = "caapf-controller-leader-election")]
+    pub leader_election_id: String,
+
+    /// Duration in seconds standby replicas wait before forcing leadership acquisition
+    #[arg(long, default_value_t = 15)]
+    pub leader_election_lease_duration: u64,
+
+    /// Duration in seconds the leader keeps retrying the lease renewal before giving up
+    #[arg(long, default_value_t = 10)]
+    pub leader_election_renew_deadline: u64,
+
+    /// Interval in seconds between lease acquisition or renewal attempts
+    #[arg(long, default_value_t = 2)]
+    pub leader_election_retry_period: u64,
+
+    /// Namespace of the Fleet controller installation and its `fleet-controller` ConfigMap
+    #[arg(long, default_value = "cattle-fleet-system")]
+    pub fleet_namespace: String,
+
+    /// Serve the validating admission webhook and the runtime extension hooks over TLS
+    /// with a self-managed serving certificate
+    #[arg(long)]
+    pub webhook: bool,
+
+    /// Port of the admission webhook and runtime extension server
+    #[arg(long, default_value_t = 9443)]
+    pub webhook_port: u16,
+
+    /// Name of the webhook Service, used for the serving certificate DNS names
+    #[arg(long, default_value = "caapf-webhook-service")]
+    pub webhook_service_name: String,
+
+    /// Name of the Secret storing the webhook serving certificate
+    #[arg(long, default_value = "caapf-webhook-service-cert")]
+    pub webhook_secret_name: String,
+
+    /// Name of the `ValidatingWebhookConfiguration` receiving the certificate CA bundle
+    #[arg(long, default_value = "caapf-validating-webhook-configuration")]
+    pub webhook_configuration_name: String,
+}
+
+/// Namespace the controller is running in.
+#[must_use]
+pub fn controller_namespace(client: &Client) -> String {
+    std::env::var("POD_NAMESPACE").unwrap_or_else(|_| client.default_namespace().to_string())
 }
 
 impl State {
@@ -74,7 +145,7 @@ impl State {
     ///
     /// Panics if the default metrics cannot be registered with the registry.
     #[must_use]
-    pub fn new(version: u32) -> Self {
+    pub fn new(version: u32, policies: Store<FleetAddonClusterPolicy>) -> Self {
         let registry = prometheus::Registry::default();
         Self {
             metrics: Metrics::default().register(&registry).unwrap(),
@@ -85,15 +156,36 @@ impl State {
             stream: BroadcastStream::new(Arc::default()),
             version,
             barrier: Arc::new(Barrier::new(3)),
+            hooks: HookTrigger::default(),
+            policies,
+            capi_version: CapiVersion::default(),
         }
     }
 
+    /// Cluster policies store
+    #[must_use]
+    pub fn policies(&self) -> &Store<FleetAddonClusterPolicy> {
+        &self.policies
+    }
+
+    /// Lifecycle hooks reconcile trigger
+    #[must_use]
+    pub fn hooks(&self) -> &HookTrigger {
+        &self.hooks
+    }
+
     /// Metrics getter
     #[must_use]
     pub fn metrics(&self) -> Vec<prometheus::proto::MetricFamily> {
         self.registry.gather()
     }
 
+    /// Leader election status gauge
+    #[must_use]
+    pub fn leader_metric(&self) -> prometheus::IntGauge {
+        self.metrics.leader.clone()
+    }
+
     /// State getter
     pub async fn diagnostics(&self) -> Diagnostics {
         self.diagnostics.read().await.clone()
@@ -102,6 +194,7 @@ impl State {
     // Create a Controller Context that can update State
     #[must_use]
     pub fn to_context(&self, client: Client) -> Arc<Context> {
+        let controller_namespace = controller_namespace(&client);
         Arc::new(Context {
             client,
             metrics: self.metrics.clone(),
@@ -110,6 +203,10 @@ impl State {
             stream: self.stream.clone(),
             version: self.version,
             barrier: self.barrier.clone(),
+            fleet_namespace: self.flags.fleet_namespace.clone(),
+            controller_namespace,
+            policies: self.policies.clone(),
+            capi_version: self.capi_version,
         })
     }
 }
@@ -287,6 +384,26 @@ pub async fn run_fleet_helm_controller(state: State) {
     tokio::join!(fleet_addon_config_controller);
 }
 
+/// Reflect cluster policies into the shared store. Runs on every replica, as the lifecycle
+/// hooks are served regardless of the leader election.
+///
+/// # Panics
+///
+/// Panics if the kube Client cannot be created.
+pub async fn run_policy_reflector(writer: Writer<FleetAddonClusterPolicy>) {
+    let client = Client::try_default()
+        .await
+        .expect("failed to create kube Client");
+
+    watcher(
+        Api::<FleetAddonClusterPolicy>::all(client),
+        Config::default().any_semantic(),
+    )
+    .default_with_reflect(writer)
+    .for_each(|_| futures::future::ready(()))
+    .await;
+}
+
 /// Initialize the controller and shared state (given the crd is installed)
 ///
 /// # Panics
@@ -308,10 +425,19 @@ pub async fn run_cluster_controller(state: State) {
         .default_backoff()
         .for_each(|_| futures::future::ready(()));
 
+    let (fleet_reader, fleet_writer) = reflector::store();
     let fleet = metadata_watcher(
         Api::<fleet_cluster::Cluster>::all(client.clone()),
         Config::default().any_semantic(),
     )
+    .default_with_reflect(fleet_writer);
+
+    let bundle_deployments = metadata_watcher(
+        Api::<BundleDeployment>::all(client.clone()),
+        Config::default()
+            .labels(BUNDLE_DEPLOYMENT_CLUSTER_LABEL)
+            .any_semantic(),
+    )
     .default_handling();
 
     let groups = metadata_watcher(
@@ -328,20 +454,57 @@ pub async fn run_cluster_controller(state: State) {
     )
     .default_handling();
 
+    let policies = metadata_watcher(
+        Api::<FleetAddonClusterPolicy>::all(client.clone()),
+        Config::default().any_semantic(),
+    )
+    .default_handling();
+
+    // CAPI kubeconfig secrets, to redeploy the fleet agent once the credentials rotate
+    let kubeconfigs = metadata_watcher(
+        Api::<Secret>::all(client.clone()),
+        Config::default()
+            .labels(CAPI_CLUSTER_NAME_LABEL)
+            .any_semantic(),
+    )
+    .default_handling();
+
     let (sub, reader) = state.dispatcher.subscribe();
+    let policy_reader = reader.clone();
     let clusters = Controller::for_shared_stream(sub, reader.clone())
-        .owns_stream(fleet)
+        .watches_stream(fleet, |fleet| origin_cluster(&fleet))
+        .watches_stream(bundle_deployments, move |deployment| {
+            let labels = deployment.labels();
+            let fleet = fleet_reader.get(
+                &ObjectRef::new(labels.get(BUNDLE_DEPLOYMENT_CLUSTER_LABEL)?)
+                    .within(labels.get(BUNDLE_DEPLOYMENT_CLUSTER_NAMESPACE_LABEL)?),
+            )?;
+            origin_cluster(&fleet)
+        })
         .owns_stream(groups)
+        .watches_stream(kubeconfigs, |secret| {
+            let cluster = secret.labels().get(CAPI_CLUSTER_NAME_LABEL)?;
+            let namespace = secret.namespace()?;
+            (secret.name_any() == format!("{cluster}-kubeconfig"))
+                .then(|| ObjectRef::new(cluster).within(&namespace))
+        })
+        .watches_stream(policies, move |_| {
+            policy_reader
+                .state()
+                .into_iter()
+                .map(|c: Arc<Cluster>| ObjectRef::from_obj(&*c))
+        })
         .watches_stream(mappings, move |mapping| {
             reader
                 .state()
                 .into_iter()
                 .filter_map(move |c: Arc<Cluster>| {
                     let in_namespace =
-                        c.spec.proxy.topology.as_ref()?.class_namespace == mapping.namespace();
+                        c.cluster_class_namespace() == mapping.namespace().as_deref();
                     in_namespace.then_some(ObjectRef::from_obj(&*c))
                 })
         })
+        .reconcile_on(state.hooks.subscribe())
         .shutdown_on_signal()
         .run(
             Cluster::reconcile,
@@ -351,10 +514,57 @@ pub async fn run_cluster_controller(state: State) {
         .default_backoff()
         .for_each(|_| futures::future::ready(()));
 
+    // Clusters leaving the import scope are no longer visible to the cluster controller,
+    // un-import is driven by the metadata of all clusters instead
+    let (unimport_reader, unimport_writer) = reflector::store();
+    let all_clusters = metadata_watcher(
+        Api::<Cluster>::all(client.clone()),
+        Config::default().any_semantic(),
+    )
+    .default_with_reflect(unimport_writer);
+
+    let namespaces = metadata_watcher(
+        Api::<Namespace>::all(client.clone()),
+        Config::default().any_semantic(),
+    )
+    .default_handling();
+
+    let configs = metadata_watcher(
+        Api::<FleetAddonConfig>::all(client.clone()),
+        Config::default().any_semantic(),
+    )
+    .default_handling();
+
+    let ns_reader = unimport_reader.clone();
+    let config_reader = unimport_reader.clone();
+    let unimport = Controller::for_stream(all_clusters, unimport_reader)
+        .watches_stream(namespaces, move |ns| {
+            let namespace = ns.name_any();
+            ns_reader
+                .state()
+                .into_iter()
+                .filter(move |c| c.namespace().as_ref() == Some(&namespace))
+                .map(|c| ObjectRef::from_obj(&*c))
+        })
+        .watches_stream(configs, move |_| {
+            config_reader
+                .state()
+                .into_iter()
+                .map(|c| ObjectRef::from_obj(&*c))
+        })
+        .shutdown_on_signal()
+        .run(
+            Cluster::reconcile_unimport,
+            error_policy,
+            state.to_context(client.clone()),
+        )
+        .default_backoff()
+        .for_each(|_| futures::future::ready(()));
+
     // Signal that this controller is ready
     state.barrier.wait().await;
 
-    tokio::join!(clusters, ns_controller);
+    tokio::join!(clusters, ns_controller, unimport);
 }
 
 /// Initialize the controller and shared state (given the crd is installed)
@@ -414,6 +624,23 @@ pub async fn run_cluster_class_controller(state: State) {
     tokio::join!(group_controller, cluster_class_controller);
 }
 
+/// Find the CAPI cluster a Fleet cluster was imported from. Fleet clusters may be renamed,
+/// so they are mapped by the origin cluster label, falling back to the owner reference.
+fn origin_cluster(fleet: &PartialObjectMeta<fleet_cluster::Cluster>) -> Option<ObjectRef<Cluster>> {
+    let name = fleet
+        .labels()
+        .get(CLUSTER_NAME_LABEL)
+        .cloned()
+        .or_else(|| {
+            fleet
+                .owner_references()
+                .iter()
+                .find(|r| r.kind == Cluster::kind(&()))
+                .map(|r| r.name.clone())
+        })?;
+    Some(ObjectRef::new(&name).within(&fleet.namespace()?))
+}
+
 #[allow(clippy::needless_pass_by_value)]
 fn error_policy(doc: Arc<impl kube::Resource>, error: &Error, ctx: Arc<Context>) -> Action {
     warn!("reconcile failed: {:?}", error);
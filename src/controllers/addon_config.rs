@@ -114,7 +114,7 @@ impl FleetAddonConfig {
 
         let chart = FleetChart {
             repo: "https://rancher.github.io/fleet-helm-charts/".into(),
-            namespace: "cattle-fleet-system".into(),
+            namespace: ctx.fleet_namespace.clone(),
             wait: true,
             update_dependency: true,
             create_namespace: true,
@@ -156,7 +156,7 @@ impl FleetAddonConfig {
         ctx: Arc<Context>,
     ) -> ReconcileConfigSyncResult<Action> {
         let _current = Span::current().record("reconcile_id", display(telemetry::get_trace_id()));
-        let ns = Namespace::from("cattle-fleet-system");
+        let ns = Namespace::from(ctx.fleet_namespace.clone());
         let mut fleet_config: FleetConfig = ctx.client.get("fleet-controller", &ns).await?;
 
         if let Some(server) = self.spec().config.as_ref().and_then(|c| c.server.as_ref()) {
@@ -1,21 +1,47 @@
 use crate::api::bundle_namespace_mapping::BundleNamespaceMapping;
-use crate::api::capi_cluster::{Cluster, FLEET_WORKSPACE_ANNOTATION};
+use crate::api::capi_cluster::{
+    CLIENT_ID_ANNOTATION, CLUSTER_NAME_LABEL, Cluster, ClusterCondition, ClusterReference,
+    DRAIN_ANNOTATION, FLEET_AGENT_DELIVERED_CONDITION, FLEET_AGENT_READY_CONDITION,
+    FLEET_BUNDLES_READY_CONDITION, FLEET_IMPORTED_CONDITION, FLEET_WORKSPACE_ANNOTATION,
+};
+use crate::api::capi_contract::{CapiVersion, resolve_contract_reference};
 
-use crate::api::fleet_addon_config::FleetAddonConfig;
-use crate::api::fleet_cluster::{self};
+use crate::api::fleet_addon_cluster_policy::FleetAddonClusterPolicy;
+use crate::api::fleet_addon_config::{
+    AGENT_NAMESPACE, AdoptionConfig, AdoptionPolicy, AgentDelivery, ClusterConfig, DeletionPolicy,
+    DrainConfig, FleetAddonConfig,
+};
+use crate::api::fleet_agent::{AgentRegistration, resource_set_name, resource_set_resource};
+use crate::api::fleet_bundle_deployment::BundleDeployment;
+use crate::api::fleet_cluster::{
+    self, BUNDLE_DEPLOYMENT_CLUSTER_LABEL, BUNDLE_DEPLOYMENT_CLUSTER_NAMESPACE_LABEL, BundleSummary,
+};
 
-#[cfg(feature = "agent-initiated")]
 use crate::api::fleet_cluster_registration_token::ClusterRegistrationToken;
 use crate::api::fleet_clustergroup::ClusterGroup;
-use crate::controllers::addon_config::to_dynamic_event;
+use crate::api::scoped_kubeconfig::{
+    SCOPED_NAMESPACE, SCOPED_SERVICE_ACCOUNT, TOKEN_EXPIRATION_ANNOTATION, TOKEN_ISSUED_ANNOTATION,
+    default_rules, scoped_kubeconfig, service_account_rbac,
+};
+use crate::controllers::addon_config::{FleetConfig, to_dynamic_event};
 use crate::controllers::controller::GetApi;
 use futures::StreamExt as _;
-use k8s_openapi::api::core::v1::Namespace;
+use k8s_openapi::ByteString;
+use k8s_openapi::api::authentication::v1::{TokenRequest, TokenRequestSpec};
+use k8s_openapi::api::core::v1::{
+    Affinity, EnvVar, Namespace, ResourceRequirements, Secret, ServiceAccount, Toleration,
+};
+use k8s_openapi::api::rbac::v1::{ClusterRole, ClusterRoleBinding, Role, RoleBinding};
 use kube::api::{
-    ApiResource, DeleteParams, DynamicObject, GroupVersionKind, ListParams, PatchParams,
+    ApiResource, DeleteParams, DynamicObject, GroupVersionKind, ListParams, ObjectMeta,
+    PartialObjectMeta, PatchParams, PostParams,
 };
+use kube::config::{KubeConfigOptions, Kubeconfig};
 
+use base64::prelude::*;
+use chrono::{DateTime, TimeDelta, Utc};
 use kube::client::scope;
+use kube::runtime::events::{Event, EventType};
 use kube::runtime::watcher::{self, Config};
 use kube::{Api, Client};
 use kube::{
@@ -25,24 +51,39 @@ use kube::{
 };
 use serde::Serialize;
 use serde_json::{Value, json};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
+use std::collections::BTreeMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 use super::controller::{
-    Context, FleetBundle, FleetController, fetch_config, get_or_create, patch,
+    Context, FLEET_FINALIZER, FleetBundle, FleetController, cached_policies, delete_opt,
+    fetch_config, get_or_create, patch, publish_event, remove_owner,
+};
+use super::{
+    BundleError, BundleResult, ClusterSyncError, ClusterSyncResult, LabelCheckError, SyncError,
 };
-use super::{BundleResult, ClusterSyncError, ClusterSyncResult};
 
 pub static CONTROLPLANE_INITIALIZED_CONDITION: &str = "ControlPlaneInitialized";
+pub static AVAILABLE_CONDITION: &str = "Available";
+
+/// Interval to check the registration token, until Fleet reports its expiration.
+const TOKEN_STATUS_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Interval to check the progress of a cluster upgrade, while the Fleet cluster is paused.
+const UPGRADE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Attempts to patch the conditions, while CAPI updates the cluster status concurrently.
+const CONDITIONS_PATCH_ATTEMPTS: usize = 3;
 
 pub struct FleetClusterBundle {
+    cluster: Cluster,
     namespace: Namespace,
     template_sources: TemplateSources,
     fleet: fleet_cluster::Cluster,
     fleet_group: Option<ClusterGroup>,
     mapping: Option<BundleNamespaceMapping>,
-    #[cfg(feature = "agent-initiated")]
     cluster_registration_token: Option<ClusterRegistrationToken>,
     config: FleetAddonConfig,
 }
@@ -64,7 +105,7 @@ impl TemplateSources {
         TemplateSources(cluster.clone())
     }
 
-    async fn resolve(&self, client: Client) -> Option<Value> {
+    async fn resolve(&self, client: Client, capi_version: CapiVersion) -> Option<Value> {
         // We need to remove all dynamic or unnessesary values from these resources
         let mut cluster = self.0.clone();
 
@@ -72,64 +113,1567 @@ impl TemplateSources {
         cluster.meta_mut().managed_fields = None;
         cluster.meta_mut().resource_version = None;
 
-        let reference = self.0.spec.proxy.control_plane_ref.as_ref()?;
-        let api_version = reference.api_version.as_ref()?;
-        let (group, version) = api_version.split_once('/').unwrap_or(("", api_version));
-        let resource = ApiResource::from_gvk(&GroupVersionKind::gvk(
-            group,
-            version,
-            reference.kind.as_ref()?,
-        ));
-        let api = Api::<DynamicObject>::namespaced_with(
+        let namespace = cluster.namespace().unwrap_or_default();
+        let control_plane = Self::resolve_reference(
             client.clone(),
-            reference.namespace.as_ref()?,
-            &resource,
-        );
-        let mut control_plane = api.get(reference.name.as_ref()?).await.ok()?;
+            capi_version,
+            self.0.spec.control_plane_ref.as_ref()?,
+            &namespace,
+        )
+        .await?;
+        let infrastructure_cluster = Self::resolve_reference(
+            client,
+            capi_version,
+            self.0.spec.infrastructure_ref.as_ref()?,
+            &namespace,
+        )
+        .await?;
+
+        let values = TemplateValues {
+            cluster,
+            control_plane,
+            infrastructure_cluster,
+        };
+
+        serde_json::to_value(values).ok()
+    }
+
+    /// Fetch the referenced object, without the status and dynamic metadata.
+    async fn resolve_reference(
+        client: Client,
+        capi_version: CapiVersion,
+        reference: &ClusterReference,
+        namespace: &str,
+    ) -> Option<DynamicObject> {
+        let mut object = Self::fetch_reference(client, capi_version, reference, namespace).await?;
 
-        if let Some(data_object) = control_plane.data.as_object_mut() {
+        if let Some(data_object) = object.data.as_object_mut() {
             data_object.remove("status");
         }
-        control_plane.meta_mut().managed_fields = None;
-        control_plane.meta_mut().resource_version = None;
-
-        let infra_reference = self.0.spec.proxy.infrastructure_ref.as_ref()?;
-        let api_version = infra_reference.api_version.as_ref()?;
-        let (group, version) = api_version.split_once('/').unwrap_or(("", api_version));
-        let resource = ApiResource::from_gvk(&GroupVersionKind::gvk(
-            group,
-            version,
-            infra_reference.kind.as_ref()?,
-        ));
-        let api = Api::<DynamicObject>::namespaced_with(
-            client.clone(),
-            infra_reference.namespace.as_ref()?,
-            &resource,
+        object.meta_mut().managed_fields = None;
+        object.meta_mut().resource_version = None;
+
+        Some(object)
+    }
+
+    /// Fetch the referenced object. `v1beta2` contract references carry no `apiVersion` and
+    /// no namespace, so the version is resolved from the CAPI contract label on the provider CRD,
+    /// and the object is looked up in the cluster namespace.
+    async fn fetch_reference(
+        client: Client,
+        capi_version: CapiVersion,
+        reference: &ClusterReference,
+        namespace: &str,
+    ) -> Option<DynamicObject> {
+        let kind = reference.kind.as_ref()?;
+        let resource = match (&reference.api_version, &reference.api_group) {
+            (Some(api_version), _) => {
+                let (group, version) = api_version.split_once('/').unwrap_or(("", api_version));
+                ApiResource::from_gvk(&GroupVersionKind::gvk(group, version, kind))
+            }
+            (None, Some(api_group)) => {
+                resolve_contract_reference(client.clone(), capi_version, api_group, kind)
+                    .await
+                    .ok()?
+            }
+            (None, None) => return None,
+        };
+
+        let namespace = reference.namespace.as_deref().unwrap_or(namespace);
+        let api = Api::<DynamicObject>::namespaced_with(client, namespace, &resource);
+        api.get(reference.name.as_ref()?).await.ok()
+    }
+}
+
+impl FleetClusterBundle {
+    /// Find a pre-existing Fleet cluster for the CAPI cluster, marked for adoption or registered
+    /// under the Fleet cluster name. Fleet clusters imported from a different CAPI cluster are
+    /// not considered.
+    async fn adoption_candidate(
+        &self,
+        ctx: Arc<Context>,
+        adoption: &AdoptionConfig,
+    ) -> ClusterSyncResult<Option<PartialObjectMeta<fleet_cluster::Cluster>>> {
+        let cluster_name = self.cluster.name_any();
+        let candidates: Vec<_> =
+            fleet_cluster::Cluster::get_api(ctx.client.clone(), self.fleet.get_namespace())
+                .list_metadata(&ListParams::default())
+                .await
+                .map_err(ClusterSyncError::ClusterLookupError)?
+                .items
+                .into_iter()
+                .filter(|fleet| {
+                    fleet
+                        .labels()
+                        .get(CLUSTER_NAME_LABEL)
+                        .is_none_or(|owner| *owner == cluster_name)
+                })
+                .collect();
+
+        let owned = candidates.iter().position(|fleet| self.owns(fleet));
+        let marked = candidates
+            .iter()
+            .position(|fleet| adoption.matches(fleet, &cluster_name));
+        let named = candidates
+            .iter()
+            .position(|fleet| fleet.name_any() == self.fleet.name_any());
+
+        Ok(owned.or(marked).or(named).map(|i| candidates[i].clone()))
+    }
+
+    /// Check if the Fleet cluster was imported from the CAPI cluster. Fleet clusters created
+    /// before the cluster name label was introduced are only owner referenced.
+    fn owns(&self, fleet: &PartialObjectMeta<fleet_cluster::Cluster>) -> bool {
+        fleet.labels().contains_key(CLUSTER_NAME_LABEL)
+            || self.cluster.uid().is_some_and(|uid| {
+                fleet
+                    .owner_references()
+                    .iter()
+                    .any(|owner| owner.uid == uid)
+            })
+    }
+
+    /// Apply the adoption policy to a pre-existing Fleet cluster. Adopted clusters are
+    /// re-parented to the CAPI cluster, and keep their name. Returns `false` if the CAPI
+    /// cluster should not be imported.
+    async fn sync_adoption(
+        &mut self,
+        ctx: Arc<Context>,
+        adoption: &AdoptionConfig,
+    ) -> ClusterSyncResult<bool> {
+        let Some(existing) = self.adoption_candidate(ctx.clone(), adoption).await? else {
+            return Ok(true);
+        };
+
+        let fleet_name = existing.name_any();
+        if self.owns(&existing) {
+            if !existing.labels().contains_key(CLUSTER_NAME_LABEL) {
+                let patch = json!({
+                    "metadata": {
+                        "labels": {CLUSTER_NAME_LABEL: self.cluster.name_any()},
+                    }
+                });
+                fleet_cluster::Cluster::get_api(ctx.client.clone(), self.fleet.get_namespace())
+                    .patch_metadata(&fleet_name, &PatchParams::default(), &Patch::Merge(&patch))
+                    .await
+                    .map_err(ClusterSyncError::AdoptionPatchError)?;
+            }
+
+            self.fleet.metadata.name = Some(fleet_name);
+            return Ok(true);
+        }
+
+        let (type_, reason, note) = match adoption.policy {
+            AdoptionPolicy::Adopt => {
+                let mut owner_references = existing.owner_references().to_vec();
+                if self.deletion_policy() == DeletionPolicy::Delete {
+                    owner_references.extend(self.cluster.owner_ref(&()));
+                }
+                let patch = json!({
+                    "metadata": {
+                        "labels": {CLUSTER_NAME_LABEL: self.cluster.name_any()},
+                        "ownerReferences": owner_references,
+                    }
+                });
+                fleet_cluster::Cluster::get_api(ctx.client.clone(), self.fleet.get_namespace())
+                    .patch_metadata(&fleet_name, &PatchParams::default(), &Patch::Merge(&patch))
+                    .await
+                    .map_err(ClusterSyncError::AdoptionPatchError)?;
+
+                (
+                    EventType::Normal,
+                    "Adopted",
+                    format!("Adopted existing fleet cluster `{fleet_name}`"),
+                )
+            }
+            AdoptionPolicy::Skip => (
+                EventType::Normal,
+                "AdoptionSkipped",
+                format!("Fleet cluster `{fleet_name}` already exists, skipping the import"),
+            ),
+            AdoptionPolicy::Fail => (
+                EventType::Warning,
+                "AdoptionRefused",
+                format!("Fleet cluster `{fleet_name}` already exists, refusing the import"),
+            ),
+        };
+
+        info!("{note}");
+        publish_event(
+            &ctx,
+            &Event {
+                type_,
+                reason: reason.into(),
+                note: Some(note.clone()),
+                action: "Creating".into(),
+                secondary: Some(existing.object_ref(&())),
+            },
+            &self.cluster.object_ref(&()),
+        )
+        .await
+        .map_err(ClusterSyncError::Event)?;
+
+        match adoption.policy {
+            AdoptionPolicy::Adopt => {
+                self.fleet.metadata.name = Some(fleet_name);
+                Ok(true)
+            }
+            AdoptionPolicy::Skip => {
+                let condition =
+                    ClusterCondition::not_ready(FLEET_IMPORTED_CONDITION, "Info", reason, note);
+                self.patch_conditions(ctx, &[condition]).await?;
+                Ok(false)
+            }
+            AdoptionPolicy::Fail => {
+                let condition =
+                    ClusterCondition::not_ready(FLEET_IMPORTED_CONDITION, "Error", reason, note);
+                self.patch_conditions(ctx, &[condition]).await?;
+                Err(ClusterSyncError::AdoptionRefused(fleet_name))
+            }
+        }
+    }
+
+    fn deletion_policy(&self) -> DeletionPolicy {
+        self.cluster
+            .deletion_policy(self.config.spec.cluster.as_ref())
+    }
+
+    /// Remove the owner references to the CAPI cluster from the Fleet objects, so they are
+    /// not garbage collected once the CAPI cluster is deleted.
+    async fn orphan(&self, ctx: Arc<Context>) -> ClusterSyncResult<()> {
+        let Some(uid) = self.cluster.uid() else {
+            return Ok(());
+        };
+        let namespace = self.cluster.get_namespace();
+
+        remove_owner(
+            &fleet_cluster::Cluster::get_api(ctx.client.clone(), namespace),
+            &self.fleet.name_any(),
+            &uid,
+        )
+        .await
+        .map_err(ClusterSyncError::OrphanPatchError)?;
+
+        if let Some(group) = self.fleet_group.as_ref() {
+            remove_owner(
+                &ClusterGroup::get_api(ctx.client.clone(), namespace),
+                &group.name_any(),
+                &uid,
+            )
+            .await
+            .map_err(ClusterSyncError::OrphanPatchError)?;
+        }
+
+        // The CAPI kubeconfig secret is owned by CAPI, only the scoped one is created here
+        if self.config.scoped_kubeconfig().is_some() {
+            if let Some(secret) = self.fleet.spec.kube_config_secret.as_ref() {
+                remove_owner(
+                    &Secret::get_api(ctx.client.clone(), namespace),
+                    secret,
+                    &uid,
+                )
+                .await
+                .map_err(ClusterSyncError::OrphanPatchError)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Ensure the Fleet cluster name is not already taken by a Fleet cluster imported
+    /// from a different CAPI cluster.
+    async fn check_name_collision(&self, ctx: Arc<Context>) -> ClusterSyncResult<()> {
+        let fleet_name = self.fleet.name_any();
+        let Some(existing) =
+            fleet_cluster::Cluster::get_api(ctx.client.clone(), self.fleet.get_namespace())
+                .get_metadata_opt(&fleet_name)
+                .await
+                .map_err(ClusterSyncError::ClusterLookupError)?
+        else {
+            return Ok(());
+        };
+
+        let owner = match existing.labels().get(CLUSTER_NAME_LABEL) {
+            Some(owner) if *owner != self.cluster.name_any() => owner.clone(),
+            _ => return Ok(()),
+        };
+
+        publish_event(
+            &ctx,
+            &Event {
+                type_: EventType::Warning,
+                reason: "NameCollision".into(),
+                note: Some(format!(
+                    "Fleet cluster `{fleet_name}` is already imported from cluster `{owner}`"
+                )),
+                action: "Creating".into(),
+                secondary: Some(existing.object_ref(&())),
+            },
+            &self.cluster.object_ref(&()),
+        )
+        .await
+        .map_err(ClusterSyncError::Event)?;
+
+        Err(ClusterSyncError::NameCollision(fleet_name, owner))
+    }
+
+    /// Report invalid per-cluster agent overrides, which are skipped for the Fleet cluster.
+    async fn report_agent_overrides(&self, ctx: Arc<Context>) -> ClusterSyncResult<()> {
+        for error in self
+            .cluster
+            .apply_agent_overrides(&mut ClusterConfig::default())
+        {
+            publish_event(
+                &ctx,
+                &Event {
+                    type_: EventType::Warning,
+                    reason: "InvalidAgentOverride".into(),
+                    note: Some(error.to_string()),
+                    action: "Updating".into(),
+                    secondary: None,
+                },
+                &self.cluster.object_ref(&()),
+            )
+            .await
+            .map_err(ClusterSyncError::Event)?;
+        }
+
+        Ok(())
+    }
+
+    /// Check if the cluster topology is being upgraded, when pausing during upgrades is enabled.
+    async fn upgrading(&self, ctx: Arc<Context>) -> bool {
+        if !self.config.pause_during_upgrade() || self.cluster.topology_version().is_none() {
+            return false;
+        }
+
+        let control_plane = match self.cluster.spec.control_plane_ref.as_ref() {
+            Some(reference) => {
+                TemplateSources::fetch_reference(
+                    ctx.client.clone(),
+                    ctx.capi_version,
+                    reference,
+                    self.cluster.get_namespace(),
+                )
+                .await
+            }
+            None => None,
+        };
+        let version = control_plane
+            .as_ref()
+            .and_then(|cp| cp.data.pointer("/status/version"))
+            .and_then(Value::as_str);
+
+        self.cluster.upgrading(version)
+    }
+
+    /// Propagate the CAPI cluster pause to the Fleet cluster, also pausing it during topology
+    /// upgrades. Returns `true` if the cluster is paused, in which case no other Fleet object
+    /// should be mutated.
+    async fn sync_pause(&self, ctx: Arc<Context>, upgrading: bool) -> ClusterSyncResult<bool> {
+        let paused = self.cluster.paused() || upgrading;
+        let api = fleet_cluster::Cluster::get_api(ctx.client.clone(), self.fleet.get_namespace());
+        let existing = api
+            .get_opt(&self.fleet.name_any())
+            .await
+            .map_err(ClusterSyncError::ClusterLookupError)?;
+
+        // Fleet clusters imported from a different CAPI cluster are left untouched
+        let Some(existing) = existing.filter(|fleet| {
+            fleet
+                .labels()
+                .get(CLUSTER_NAME_LABEL)
+                .is_none_or(|owner| *owner == self.cluster.name_any())
+        }) else {
+            return Ok(paused);
+        };
+
+        if existing.spec.paused.unwrap_or_default() == paused {
+            return Ok(paused);
+        }
+
+        let fleet_name = existing.name_any();
+        api.patch(
+            &fleet_name,
+            &PatchParams::default(),
+            &Patch::Merge(json!({"spec": {"paused": paused}})),
+        )
+        .await
+        .map_err(ClusterSyncError::PausePatchError)?;
+
+        let (reason, note) = if upgrading && !self.cluster.paused() {
+            (
+                "UpgradePaused",
+                format!(
+                    "Paused fleet cluster `{fleet_name}` during the upgrade to {}",
+                    self.cluster.topology_version().unwrap_or_default()
+                ),
+            )
+        } else if paused {
+            ("Paused", format!("Paused fleet cluster `{fleet_name}`"))
+        } else {
+            ("Resumed", format!("Resumed fleet cluster `{fleet_name}`"))
+        };
+        info!("{note}");
+        publish_event(
+            &ctx,
+            &Event {
+                type_: EventType::Normal,
+                reason: reason.into(),
+                note: Some(note),
+                action: "Updating".into(),
+                secondary: Some(existing.object_ref(&())),
+            },
+            &self.cluster.object_ref(&()),
+        )
+        .await
+        .map_err(ClusterSyncError::Event)?;
+
+        Ok(paused)
+    }
+
+    /// Bump the Fleet cluster `redeployAgentGeneration` when the CAPI kubeconfig secret data
+    /// changes, or when the redeploy annotation on the CAPI cluster is set or updated.
+    async fn sync_agent_redeploy(&mut self, ctx: Arc<Context>) -> ClusterSyncResult<()> {
+        if self.fleet.spec.kube_config_secret.is_none() {
+            return Ok(());
+        }
+
+        let secret_name = format!("{}-kubeconfig", self.cluster.name_any());
+        let secret = Secret::get_api(ctx.client.clone(), self.cluster.get_namespace())
+            .get_opt(&secret_name)
+            .await
+            .map_err(ClusterSyncError::ClusterLookupError)?;
+        if let Some(ByteString(kubeconfig)) = secret
+            .as_ref()
+            .and_then(|s| s.data.as_ref())
+            .and_then(|d| d.get("value"))
+        {
+            self.fleet.set_kubeconfig_hash(kubeconfig);
+        }
+
+        let api = fleet_cluster::Cluster::get_api(ctx.client.clone(), self.fleet.get_namespace());
+        let Some(existing) = api
+            .get_opt(&self.fleet.name_any())
+            .await
+            .map_err(ClusterSyncError::ClusterLookupError)?
+        else {
+            return Ok(());
+        };
+
+        let (changed, reason) = self.fleet.agent_redeploy(&existing);
+        if changed.is_empty() {
+            return Ok(());
+        }
+
+        let mut patch = json!({"metadata": {"annotations": changed}});
+        if reason.is_some() {
+            let generation = existing.spec.redeploy_agent_generation.unwrap_or_default() + 1;
+            patch["spec"] = json!({"redeployAgentGeneration": generation});
+        }
+
+        let fleet_name = existing.name_any();
+        api.patch(&fleet_name, &PatchParams::default(), &Patch::Merge(&patch))
+            .await
+            .map_err(ClusterSyncError::RedeployPatchError)?;
+
+        let Some(reason) = reason else {
+            return Ok(());
+        };
+        info!("Redeploying fleet agent of cluster `{fleet_name}`: {reason}");
+        publish_event(
+            &ctx,
+            &Event {
+                type_: EventType::Normal,
+                reason: reason.into(),
+                note: Some(format!("Redeploying fleet agent of cluster `{fleet_name}`")),
+                action: "Updating".into(),
+                secondary: Some(existing.object_ref(&())),
+            },
+            &self.cluster.object_ref(&()),
+        )
+        .await
+        .map_err(ClusterSyncError::Event)?;
+
+        Ok(())
+    }
+
+    /// Persist the agent client ID on the CAPI cluster, keeping it stable across reconciles.
+    /// The client ID of an already imported Fleet cluster is preserved.
+    async fn persist_client_id(&mut self, ctx: Arc<Context>) -> ClusterSyncResult<()> {
+        if self.fleet.spec.client_id.is_none()
+            || self
+                .cluster
+                .annotations()
+                .contains_key(CLIENT_ID_ANNOTATION)
+        {
+            return Ok(());
+        }
+
+        let existing =
+            fleet_cluster::Cluster::get_api(ctx.client.clone(), self.fleet.get_namespace())
+                .get_opt(&self.fleet.name_any())
+                .await
+                .map_err(ClusterSyncError::ClusterLookupError)?;
+        if let Some(client_id) = existing.and_then(|fleet| fleet.spec.client_id) {
+            self.fleet.spec.client_id = Some(client_id);
+        }
+
+        let client_id = self.fleet.spec.client_id.clone().unwrap_or_default();
+        let patch = json!({
+            "metadata": {
+                "annotations": {
+                    CLIENT_ID_ANNOTATION: client_id,
+                }
+            }
+        });
+        Cluster::get_api(ctx.client.clone(), self.cluster.get_namespace())
+            .patch_metadata(
+                &self.cluster.name_any(),
+                &PatchParams::default(),
+                &Patch::Merge(&patch),
+            )
+            .await
+            .map_err(ClusterSyncError::ClientIdPatchError)?;
+
+        self.fleet
+            .annotations_mut()
+            .insert(CLIENT_ID_ANNOTATION.to_string(), client_id);
+
+        Ok(())
+    }
+
+    /// Manage the `ClusterRegistrationToken` of an agent initiated cluster. The token is
+    /// regenerated once expired or when the TTL changes, and removed after the agent registers.
+    /// Returns the action requeueing the cluster at the token expiration.
+    async fn sync_registration_token(
+        &self,
+        ctx: Arc<Context>,
+        registered: bool,
+    ) -> ClusterSyncResult<Action> {
+        let Some(token) = self.cluster_registration_token.as_ref() else {
+            return Ok(Action::await_change());
+        };
+
+        let api = ClusterRegistrationToken::get_api(ctx.client.clone(), token.get_namespace());
+        let existing = api
+            .get_opt(&token.name_any())
+            .await
+            .map_err(ClusterSyncError::RegistrationTokenError)?;
+
+        let (reason, note) = match existing {
+            Some(_) if registered => (
+                "RegistrationTokenRemoved",
+                format!(
+                    "Removed registration token after the agent of `{}` registered",
+                    self.fleet.name_any()
+                ),
+            ),
+            None if registered => return Ok(Action::await_change()),
+            None => {
+                get_or_create(ctx.clone(), token).await?;
+                return Ok(Action::requeue(TOKEN_STATUS_INTERVAL));
+            }
+            Some(existing) => match existing.expires() {
+                _ if existing.spec.ttl != token.spec.ttl => (
+                    "RegistrationTokenRotated",
+                    format!(
+                        "Registration token TTL changed to {}",
+                        token.spec.ttl.as_deref().unwrap_or_default()
+                    ),
+                ),
+                Some(expires) if expires <= Utc::now() => (
+                    "RegistrationTokenRotated",
+                    format!("Registration token expired at {}", expires.to_rfc3339()),
+                ),
+                Some(expires) => {
+                    let remaining = (expires - Utc::now()).to_std().unwrap_or_default();
+                    return Ok(Action::requeue(remaining));
+                }
+                None => return Ok(Action::requeue(TOKEN_STATUS_INTERVAL)),
+            },
+        };
+
+        // The token is re-created on the next reconcile, once the previous one is gone
+        delete_opt(&api, &token.name_any())
+            .await
+            .map_err(ClusterSyncError::RegistrationTokenError)?;
+        info!("{note} for cluster {}", self.cluster.name_any());
+
+        publish_event(
+            &ctx,
+            &Event {
+                type_: EventType::Normal,
+                reason: reason.into(),
+                note: Some(note),
+                action: "Deleting".into(),
+                secondary: Some(token.object_ref(&())),
+            },
+            &self.cluster.object_ref(&()),
+        )
+        .await
+        .map_err(ClusterSyncError::Event)?;
+
+        if registered {
+            Ok(Action::await_change())
+        } else {
+            Ok(Action::requeue(TOKEN_STATUS_INTERVAL))
+        }
+    }
+
+    /// Report the Fleet import, agent and bundle state as conditions on the CAPI cluster.
+    async fn update_conditions(
+        &self,
+        ctx: Arc<Context>,
+        delivered: Option<ClusterCondition>,
+    ) -> ClusterSyncResult<()> {
+        let fleet_api =
+            fleet_cluster::Cluster::get_api(ctx.client.clone(), self.fleet.get_namespace());
+        let fleet = fleet_api
+            .get_opt(&self.fleet.name_any())
+            .await
+            .map_err(ClusterSyncError::ClusterLookupError)?;
+
+        let kubeconfig_missing = match self.fleet.spec.kube_config_secret.as_ref() {
+            Some(secret) => Secret::get_api(ctx.client.clone(), self.fleet.get_namespace())
+                .get_metadata_opt(secret)
+                .await
+                .map_err(ClusterSyncError::ClusterLookupError)?
+                .is_none()
+                .then_some(secret),
+            None => None,
+        };
+
+        let mut conditions = match (fleet, kubeconfig_missing) {
+            (None, _) => vec![ClusterCondition::not_ready(
+                FLEET_IMPORTED_CONDITION,
+                "Info",
+                "FleetClusterNotFound",
+                format!(
+                    "Fleet cluster `{}` is not created yet",
+                    self.fleet.name_any()
+                ),
+            )],
+            (Some(fleet), Some(secret)) => vec![
+                ClusterCondition::not_ready(
+                    FLEET_IMPORTED_CONDITION,
+                    "Warning",
+                    "KubeconfigSecretMissing",
+                    format!("Kubeconfig secret `{secret}` for the agent deployment is not found"),
+                ),
+                fleet.agent_ready_condition(),
+                self.bundle_summary(ctx.clone()).await?.condition(),
+            ],
+            (Some(fleet), None) => vec![
+                ClusterCondition::ready(FLEET_IMPORTED_CONDITION, "Imported"),
+                fleet.agent_ready_condition(),
+                self.bundle_summary(ctx.clone()).await?.condition(),
+            ],
+        };
+
+        conditions.extend(delivered);
+
+        self.patch_conditions(ctx, &conditions).await
+    }
+
+    /// Aggregate the state of the `BundleDeployments` targeting the Fleet cluster,
+    /// and report the counts as metrics.
+    async fn bundle_summary(&self, ctx: Arc<Context>) -> ClusterSyncResult<BundleSummary> {
+        let deployments = self.bundle_deployments(ctx.clone()).await?;
+
+        let summary = BundleSummary::from_deployments(&deployments);
+        ctx.metrics.set_bundle_deployments(
+            self.cluster.get_namespace(),
+            &self.cluster.name_any(),
+            &summary,
         );
-        let mut infrastructure_cluster = api.get(infra_reference.name.as_ref()?).await.ok()?;
 
-        if let Some(data_object) = infrastructure_cluster.data.as_object_mut() {
-            data_object.remove("status");
+        Ok(summary)
+    }
+
+    async fn bundle_deployments(
+        &self,
+        ctx: Arc<Context>,
+    ) -> ClusterSyncResult<Vec<BundleDeployment>> {
+        let selector = format!(
+            "{BUNDLE_DEPLOYMENT_CLUSTER_NAMESPACE_LABEL}={},{BUNDLE_DEPLOYMENT_CLUSTER_LABEL}={}",
+            self.fleet.get_namespace(),
+            self.fleet.name_any()
+        );
+        let deployments = Api::<BundleDeployment>::all(ctx.client.clone())
+            .list(&ListParams::default().labels(&selector))
+            .await
+            .map_err(ClusterSyncError::BundleDeploymentLookupError)?;
+
+        Ok(deployments.items)
+    }
+
+    /// Remove the Fleet cluster from bundle targets, and hold the deletion until the agent
+    /// removed its `BundleDeployments`, or the drain timed out. The drain start is recorded
+    /// on the CAPI cluster, so the timeout is kept across controller restarts.
+    async fn drain(&self, ctx: Arc<Context>, drain: &DrainConfig) -> ClusterSyncResult<()> {
+        let api = fleet_cluster::Cluster::get_api(ctx.client.clone(), self.fleet.get_namespace());
+        let existing = api
+            .get_opt(&self.fleet.name_any())
+            .await
+            .map_err(ClusterSyncError::ClusterLookupError)?;
+
+        // Fleet clusters imported from a different CAPI cluster are left untouched
+        let Some(existing) = existing.filter(|fleet| {
+            fleet
+                .labels()
+                .get(CLUSTER_NAME_LABEL)
+                .is_none_or(|owner| *owner == self.cluster.name_any())
+        }) else {
+            return Ok(());
+        };
+
+        // Bundles target clusters by labels, directly or through cluster groups
+        let labels = existing.drain_labels();
+        if !labels.is_empty() {
+            api.patch_metadata(
+                &existing.name_any(),
+                &PatchParams::default(),
+                &Patch::Merge(json!({"metadata": {"labels": labels}})),
+            )
+            .await
+            .map_err(ClusterSyncError::DrainPatchError)?;
         }
-        infrastructure_cluster.meta_mut().managed_fields = None;
-        infrastructure_cluster.meta_mut().resource_version = None;
 
-        let values = TemplateValues {
-            cluster,
-            control_plane,
-            infrastructure_cluster,
+        let started = match self.cluster.drain_started() {
+            Some(started) => started,
+            None => {
+                let started = Utc::now();
+                Cluster::get_api(ctx.client.clone(), self.cluster.get_namespace())
+                    .patch_metadata(
+                        &self.cluster.name_any(),
+                        &PatchParams::default(),
+                        &Patch::Merge(json!({
+                            "metadata": {"annotations": {DRAIN_ANNOTATION: started.to_rfc3339()}}
+                        })),
+                    )
+                    .await
+                    .map_err(ClusterSyncError::DrainPatchError)?;
+                self.publish_drain_event(
+                    &ctx,
+                    EventType::Normal,
+                    "Draining",
+                    format!(
+                        "Removing fleet cluster `{}` from bundle targets",
+                        existing.name_any()
+                    ),
+                )
+                .await?;
+
+                // CAPI calls the BeforeClusterDelete hook for clusters with a topology only,
+                // otherwise the infrastructure is torn down while the cluster is drained
+                if self.cluster.spec.topology.is_none() {
+                    self.publish_drain_event(
+                        &ctx,
+                        EventType::Warning,
+                        "DrainNotBlocking",
+                        "Cluster has no topology, the BeforeClusterDelete hook does not hold the infrastructure deletion during the drain".into(),
+                    )
+                    .await?;
+                }
+                started
+            }
         };
 
-        serde_json::to_value(values).ok()
+        let remaining = self.bundle_deployments(ctx.clone()).await?.len();
+        if remaining == 0 {
+            return self
+                .publish_drain_event(
+                    &ctx,
+                    EventType::Normal,
+                    "Drained",
+                    format!(
+                        "Removed bundle deployments from fleet cluster `{}`",
+                        existing.name_any()
+                    ),
+                )
+                .await;
+        }
+
+        if !drain.timed_out(started, Utc::now()) {
+            return Err(ClusterSyncError::DrainPending(remaining));
+        }
+
+        warn!(
+            "Drain of fleet cluster {} timed out with {remaining} bundle deployments left",
+            existing.name_any()
+        );
+        self.publish_drain_event(
+            &ctx,
+            EventType::Warning,
+            "DrainTimedOut",
+            format!(
+                "Drain timed out after {}s, {remaining} bundle deployments were not removed from fleet cluster `{}`",
+                drain.timeout_seconds(),
+                existing.name_any()
+            ),
+        )
+        .await
+    }
+
+    async fn publish_drain_event(
+        &self,
+        ctx: &Arc<Context>,
+        type_: EventType,
+        reason: &str,
+        note: String,
+    ) -> ClusterSyncResult<()> {
+        publish_event(
+            ctx,
+            &Event {
+                type_,
+                reason: reason.into(),
+                note: Some(note),
+                action: "Deleting".into(),
+                secondary: None,
+            },
+            &self.cluster.object_ref(&()),
+        )
+        .await
+        .map_err(ClusterSyncError::Event)
+    }
+
+    /// Patch the conditions on the CAPI cluster. The resource version guards the conditions
+    /// list from concurrent CAPI status updates, so conflicts are retried on the latest cluster.
+    async fn patch_conditions(
+        &self,
+        ctx: Arc<Context>,
+        conditions: &[ClusterCondition],
+    ) -> ClusterSyncResult<()> {
+        let api = Cluster::get_api(ctx.client.clone(), self.cluster.get_namespace());
+        let mut latest = None;
+        for _ in 0..CONDITIONS_PATCH_ATTEMPTS {
+            let cluster = latest.as_ref().unwrap_or(&self.cluster);
+            let Some(merged) = cluster.merge_conditions(ctx.capi_version, conditions) else {
+                return Ok(());
+            };
+
+            let patch = json!({
+                "metadata": {
+                    "resourceVersion": cluster.resource_version(),
+                },
+                "status": {
+                    "conditions": merged,
+                }
+            });
+            match api
+                .patch_status(
+                    &self.cluster.name_any(),
+                    &PatchParams::default(),
+                    &Patch::Merge(&patch),
+                )
+                .await
+            {
+                Ok(_) => {
+                    debug!(
+                        "Updated fleet conditions on cluster {}",
+                        self.cluster.name_any()
+                    );
+                    return Ok(());
+                }
+                Err(kube::Error::Api(e)) if e.code == 409 => {
+                    debug!(
+                        "Conflict updating fleet conditions on cluster {}, retrying",
+                        self.cluster.name_any()
+                    );
+                    latest = Some(
+                        api.get(&self.cluster.name_any())
+                            .await
+                            .map_err(ClusterSyncError::ClusterLookupError)?,
+                    );
+                }
+                Err(e) => return Err(ClusterSyncError::ConditionsPatchError(e)),
+            }
+        }
+
+        // Conditions are reported again on the next reconcile
+        debug!(
+            "Fleet conditions on cluster {} are updated concurrently, skipping",
+            self.cluster.name_any()
+        );
+        Ok(())
+    }
+
+    /// Remove the fleet objects of a cluster which no longer matches the import selectors,
+    /// and release the cluster by removing the finalizer.
+    async fn unimport(&mut self, ctx: Arc<Context>) -> Result<Action, SyncError> {
+        if self.config.unimport_remove_agent_enabled() {
+            self.remove_agent(ctx.clone()).await?;
+        }
+
+        if let Some(token) = self.cluster_registration_token.as_ref() {
+            let api = ClusterRegistrationToken::get_api(ctx.client.clone(), token.get_namespace());
+            delete_opt(&api, &token.name_any())
+                .await
+                .map_err(ClusterSyncError::DeleteError)?;
+        }
+
+        // Fleet clusters imported from a different CAPI cluster are left untouched
+        let fleet_api =
+            fleet_cluster::Cluster::get_api(ctx.client.clone(), self.fleet.get_namespace());
+        let fleet = fleet_api
+            .get_metadata_opt(&self.fleet.name_any())
+            .await
+            .map_err(ClusterSyncError::ClusterLookupError)?;
+        if fleet
+            .is_some_and(|f| f.labels().get(CLUSTER_NAME_LABEL) == Some(&self.cluster.name_any()))
+        {
+            delete_opt(&fleet_api, &self.fleet.name_any())
+                .await
+                .map_err(ClusterSyncError::DeleteError)?;
+        }
+
+        if let Some(group) = self.fleet_group.as_ref() {
+            self.remove_group(ctx.clone(), group).await?;
+        }
+
+        self.cleanup(ctx.clone()).await?;
+
+        let conditions = [
+            ClusterCondition::not_ready(
+                FLEET_IMPORTED_CONDITION,
+                "Info",
+                "Unimported",
+                "Cluster no longer matches the import selectors".into(),
+            ),
+            ClusterCondition::unknown(FLEET_AGENT_READY_CONDITION, "Unimported", String::default()),
+            ClusterCondition::unknown(
+                FLEET_BUNDLES_READY_CONDITION,
+                "Unimported",
+                String::default(),
+            ),
+        ];
+        self.patch_conditions(ctx.clone(), &conditions).await?;
+        self.remove_finalizer(ctx.clone()).await?;
+
+        let note = format!(
+            "Removed fleet cluster `{}` as the cluster no longer matches the import selectors",
+            self.fleet.name_any()
+        );
+        info!("{note}");
+        publish_event(
+            &ctx,
+            &Event {
+                type_: EventType::Normal,
+                reason: "Unimported".into(),
+                note: Some(note),
+                action: "Deleting".into(),
+                secondary: None,
+            },
+            &self.cluster.object_ref(&()),
+        )
+        .await
+        .map_err(ClusterSyncError::Event)?;
+
+        Ok(Action::await_change())
+    }
+
+    /// Remove the `ClusterGroup` unless it is still used by other imported clusters
+    /// referencing the same `ClusterClass`.
+    async fn remove_group(&self, ctx: Arc<Context>, group: &ClusterGroup) -> ClusterSyncResult<()> {
+        let other_clusters = Cluster::get_api(ctx.client.clone(), self.cluster.get_namespace())
+            .list(&ListParams::default())
+            .await
+            .map_err(ClusterSyncError::ClusterLookupError)?;
+        let shared = other_clusters.iter().any(|c| {
+            c.name_any() != self.cluster.name_any()
+                && c.finalizers().iter().any(|f| f == FLEET_FINALIZER)
+                && c.cluster_class_name() == self.cluster.cluster_class_name()
+                && c.cluster_class_namespace() == self.cluster.cluster_class_namespace()
+        });
+        if shared {
+            return Ok(());
+        }
+
+        let api = ClusterGroup::get_api(ctx.client.clone(), group.get_namespace());
+        delete_opt(&api, &group.name_any())
+            .await
+            .map_err(ClusterSyncError::DeleteError)
+    }
+
+    /// Read the CAPI cluster-admin kubeconfig of the downstream cluster.
+    /// Returns `None` if the secret is not found.
+    async fn admin_kubeconfig(&self, ctx: Arc<Context>) -> ClusterSyncResult<Option<Kubeconfig>> {
+        let secret_name = format!("{}-kubeconfig", self.cluster.name_any());
+        let secret = Secret::get_api(ctx.client.clone(), self.cluster.get_namespace())
+            .get_opt(&secret_name)
+            .await
+            .map_err(ClusterSyncError::DownstreamClientError)?;
+        let Some(ByteString(kubeconfig)) = secret
+            .as_ref()
+            .and_then(|s| s.data.as_ref())
+            .and_then(|d| d.get("value"))
+        else {
+            warn!("Kubeconfig secret `{secret_name}` not found");
+            return Ok(None);
+        };
+
+        Ok(Some(Kubeconfig::from_yaml(&String::from_utf8_lossy(
+            kubeconfig,
+        ))?))
+    }
+
+    /// Build a client for the downstream cluster from the CAPI kubeconfig secret.
+    /// Returns `None` if the secret is not found.
+    async fn downstream_client(&self, ctx: Arc<Context>) -> ClusterSyncResult<Option<Client>> {
+        let Some(kubeconfig) = self.admin_kubeconfig(ctx).await? else {
+            return Ok(None);
+        };
+
+        let config =
+            kube::Config::from_custom_kubeconfig(kubeconfig, &KubeConfigOptions::default()).await?;
+        let client = Client::try_from(config).map_err(ClusterSyncError::DownstreamClientError)?;
+
+        Ok(Some(client))
+    }
+
+    /// Bootstrap the Fleet ServiceAccount in the downstream cluster, and store a kubeconfig
+    /// with its token in the secret referenced by the Fleet cluster. The kubeconfig is rotated
+    /// once half of the issued token lifetime has passed. Returns the time until the next rotation.
+    async fn sync_scoped_kubeconfig(
+        &self,
+        ctx: Arc<Context>,
+    ) -> ClusterSyncResult<Option<Duration>> {
+        let (Some(scoped), Some(secret_name)) = (
+            self.config.scoped_kubeconfig(),
+            self.fleet.spec.kube_config_secret.as_ref(),
+        ) else {
+            return Ok(None);
+        };
+
+        let lifetime = scoped.token_expiration_seconds();
+        let api = Secret::get_api(ctx.client.clone(), self.cluster.get_namespace());
+        let rotation_time = api
+            .get_metadata_opt(secret_name)
+            .await
+            .map_err(ClusterSyncError::ScopedKubeconfigError)?
+            .and_then(|secret| {
+                let annotation = |key| {
+                    secret
+                        .annotations()
+                        .get(key)
+                        .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+                        .map(|time| time.to_utc())
+                };
+                let expiration = annotation(TOKEN_EXPIRATION_ANNOTATION)?;
+
+                // The API server may issue a token shorter lived than requested
+                Some(match annotation(TOKEN_ISSUED_ANNOTATION) {
+                    Some(issued) => issued + (expiration - issued) / 2,
+                    None => expiration - TimeDelta::seconds(lifetime / 2),
+                })
+            });
+        if let Some(rotation_time) = rotation_time {
+            let until_rotation = rotation_time - Utc::now();
+            if until_rotation > TimeDelta::zero() {
+                return Ok(until_rotation.to_std().ok());
+            }
+        }
+
+        let Some(admin) = self.admin_kubeconfig(ctx.clone()).await? else {
+            return Ok(None);
+        };
+        let config =
+            kube::Config::from_custom_kubeconfig(admin.clone(), &KubeConfigOptions::default())
+                .await?;
+        let client = Client::try_from(config).map_err(ClusterSyncError::DownstreamClientError)?;
+
+        let agent_namespace = self
+            .fleet
+            .spec
+            .agent_namespace
+            .clone()
+            .unwrap_or_else(|| AGENT_NAMESPACE.to_string());
+        let rules = scoped
+            .rules
+            .clone()
+            .unwrap_or_else(|| default_rules(&agent_namespace));
+        let rbac = service_account_rbac(rules, &agent_namespace);
+        let pp = PatchParams::apply("addon-provider-fleet").force();
+        let sa_api = ServiceAccount::get_api(client.clone(), SCOPED_NAMESPACE);
+        sa_api
+            .patch(
+                SCOPED_SERVICE_ACCOUNT,
+                &pp,
+                &Patch::Apply(&rbac.service_account),
+            )
+            .await
+            .map_err(ClusterSyncError::ScopedKubeconfigError)?;
+        let cluster_roles = ClusterRole::get_api(client.clone(), &());
+        for role in [&rbac.cluster_role, &rbac.agent_role] {
+            cluster_roles
+                .patch(&role.name_any(), &pp, &Patch::Apply(role))
+                .await
+                .map_err(ClusterSyncError::ScopedKubeconfigError)?;
+        }
+        ClusterRoleBinding::get_api(client.clone(), &())
+            .patch(
+                SCOPED_SERVICE_ACCOUNT,
+                &pp,
+                &Patch::Apply(&rbac.cluster_role_binding),
+            )
+            .await
+            .map_err(ClusterSyncError::ScopedKubeconfigError)?;
+        Api::<Namespace>::all(client.clone())
+            .patch(&agent_namespace, &pp, &Patch::Apply(&rbac.namespace))
+            .await
+            .map_err(ClusterSyncError::ScopedKubeconfigError)?;
+        Api::<Role>::namespaced(client.clone(), &agent_namespace)
+            .patch(SCOPED_SERVICE_ACCOUNT, &pp, &Patch::Apply(&rbac.role))
+            .await
+            .map_err(ClusterSyncError::ScopedKubeconfigError)?;
+        Api::<RoleBinding>::namespaced(client, &agent_namespace)
+            .patch(
+                SCOPED_SERVICE_ACCOUNT,
+                &pp,
+                &Patch::Apply(&rbac.role_binding),
+            )
+            .await
+            .map_err(ClusterSyncError::ScopedKubeconfigError)?;
+
+        let issued = Utc::now();
+        let status = sa_api
+            .create_token_request(
+                SCOPED_SERVICE_ACCOUNT,
+                &PostParams::default(),
+                &TokenRequest {
+                    spec: TokenRequestSpec {
+                        expiration_seconds: Some(lifetime),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(ClusterSyncError::ScopedKubeconfigError)?
+            .status;
+        let Some(status) = status else {
+            warn!(
+                "Token request for cluster {} returned no token, skipping scoped kubeconfig",
+                self.cluster.name_any()
+            );
+            return Ok(None);
+        };
+        let Some(kubeconfig) = scoped_kubeconfig(&admin, &status.token)? else {
+            warn!(
+                "Kubeconfig of cluster {} has no cluster entry, skipping scoped kubeconfig",
+                self.cluster.name_any()
+            );
+            return Ok(None);
+        };
+
+        let secret = Secret {
+            metadata: ObjectMeta {
+                name: Some(secret_name.clone()),
+                namespace: self.cluster.namespace(),
+                annotations: Some(BTreeMap::from([
+                    (TOKEN_ISSUED_ANNOTATION.to_string(), issued.to_rfc3339()),
+                    (
+                        TOKEN_EXPIRATION_ANNOTATION.to_string(),
+                        status.expiration_timestamp.0.to_rfc3339(),
+                    ),
+                ])),
+                owner_references: self
+                    .cluster
+                    .owner_ref(&())
+                    .filter(|_| self.deletion_policy() == DeletionPolicy::Delete)
+                    .map(|owner| vec![owner]),
+                ..Default::default()
+            },
+            data: Some(BTreeMap::from([(
+                "value".to_string(),
+                ByteString(kubeconfig.into_bytes()),
+            )])),
+            ..Default::default()
+        };
+        api.patch(secret_name, &pp, &Patch::Apply(&secret))
+            .await
+            .map_err(ClusterSyncError::ScopedKubeconfigError)?;
+
+        publish_event(
+            &ctx,
+            &Event {
+                type_: EventType::Normal,
+                reason: "KubeconfigRotated".into(),
+                note: Some(format!(
+                    "Issued kubeconfig secret `{secret_name}` for the `{SCOPED_SERVICE_ACCOUNT}` ServiceAccount"
+                )),
+                action: "Updating".into(),
+                secondary: None,
+            },
+            &self.cluster.object_ref(&()),
+        )
+        .await
+        .map_err(ClusterSyncError::Event)?;
+
+        let rotation = (status.expiration_timestamp.0 - issued) / 2;
+        Ok(rotation.to_std().ok())
+    }
+
+    /// Uninstall the fleet agent by removing the agent namespace from the downstream cluster.
+    async fn remove_agent(&self, ctx: Arc<Context>) -> ClusterSyncResult<()> {
+        let Some(client) = self.downstream_client(ctx).await? else {
+            warn!(
+                "Skipping fleet agent removal from cluster {}",
+                self.cluster.name_any()
+            );
+            return Ok(());
+        };
+
+        let agent_namespace = self
+            .fleet
+            .spec
+            .agent_namespace
+            .clone()
+            .unwrap_or_else(|| AGENT_NAMESPACE.to_string());
+        delete_opt(&Api::<Namespace>::all(client), &agent_namespace)
+            .await
+            .map_err(ClusterSyncError::AgentRemovalError)?;
+
+        info!(
+            "Removed fleet agent namespace {agent_namespace} from cluster {}",
+            self.cluster.name_any()
+        );
+        Ok(())
+    }
+
+    /// Check if the fleet agent of the cluster has registered with the Fleet controller.
+    async fn agent_registered(&self, ctx: Arc<Context>) -> ClusterSyncResult<bool> {
+        Ok(
+            fleet_cluster::Cluster::get_api(ctx.client.clone(), self.fleet.get_namespace())
+                .get_opt(&self.fleet.name_any())
+                .await
+                .map_err(ClusterSyncError::ClusterLookupError)?
+                .is_some_and(|fleet| fleet.agent_registered()),
+        )
+    }
+
+    /// Collect the agent registration from the registration token secret issued by Fleet,
+    /// and the fleet-controller settings. Returns `None` until Fleet processes the token.
+    async fn agent_registration(
+        &self,
+        ctx: Arc<Context>,
+        token: &ClusterRegistrationToken,
+    ) -> ClusterSyncResult<Option<AgentRegistration>> {
+        let Some(secret_name) =
+            ClusterRegistrationToken::get_api(ctx.client.clone(), token.get_namespace())
+                .get_opt(&token.name_any())
+                .await
+                .map_err(ClusterSyncError::RegistrationTokenError)?
+                .and_then(|token| token.secret_name())
+        else {
+            return Ok(None);
+        };
+
+        let secret = Secret::get_api(ctx.client.clone(), token.get_namespace())
+            .get_opt(&secret_name)
+            .await
+            .map_err(ClusterSyncError::RegistrationTokenError)?;
+        let Some(ByteString(values)) = secret
+            .as_ref()
+            .and_then(|s| s.data.as_ref())
+            .and_then(|d| d.get("values"))
+        else {
+            return Ok(None);
+        };
+
+        let values: BTreeMap<String, serde_yaml::Value> = serde_yaml::from_slice(values)?;
+        let mut bootstrap: BTreeMap<String, String> = values
+            .into_iter()
+            .filter_map(|(key, value)| match value {
+                serde_yaml::Value::String(value) => Some((key, value)),
+                _ => None,
+            })
+            .collect();
+
+        let fleet_config: FleetConfig = ctx
+            .client
+            .get(
+                "fleet-controller",
+                &scope::Namespace::from(ctx.fleet_namespace.clone()),
+            )
+            .await
+            .map_err(ClusterSyncError::AgentDeliveryError)?;
+        let config = fleet_config.data.config;
+        if !bootstrap.contains_key("apiServerURL") {
+            bootstrap.insert("apiServerURL".into(), config.api_server_url);
+        }
+        if !bootstrap.contains_key("apiServerCA") {
+            // The fleet-controller config stores the CA base64 encoded
+            if let Ok(ca) = BASE64_STANDARD.decode(&config.api_server_ca) {
+                bootstrap.insert("apiServerCA".into(), String::from_utf8_lossy(&ca).into());
+            }
+        }
+
+        let image = config
+            .other
+            .get("agentImage")
+            .and_then(Value::as_str)
+            .filter(|image| !image.is_empty())
+            .ok_or(ClusterSyncError::AgentImageMissing)?;
+        let tolerations: Option<Vec<Toleration>> =
+            serde_json::from_value(serde_json::to_value(&self.fleet.spec.agent_tolerations)?)?;
+        let env: Option<Vec<EnvVar>> =
+            serde_json::from_value(serde_json::to_value(&self.fleet.spec.agent_env_vars)?)?;
+        let affinity: Option<Affinity> =
+            serde_json::from_value(serde_json::to_value(&self.fleet.spec.agent_affinity)?)?;
+        let resources: Option<ResourceRequirements> =
+            serde_json::from_value(serde_json::to_value(&self.fleet.spec.agent_resources)?)?;
+
+        Ok(Some(AgentRegistration {
+            namespace: self
+                .fleet
+                .spec
+                .agent_namespace
+                .clone()
+                .unwrap_or_else(|| AGENT_NAMESPACE.to_string()),
+            image: image.into(),
+            client_id: self.fleet.spec.client_id.clone(),
+            host_network: self.fleet.spec.host_network,
+            tolerations: tolerations.unwrap_or_default(),
+            env: env.unwrap_or_default(),
+            affinity,
+            resources,
+            bootstrap,
+        }))
+    }
+
+    /// Deliver the fleet agent registration into the agent initiated cluster. Returns the
+    /// `FleetAgentDelivered` condition reporting the progress, if the delivery is enabled.
+    async fn deliver_agent(
+        &self,
+        ctx: Arc<Context>,
+        registered: bool,
+    ) -> ClusterSyncResult<Option<ClusterCondition>> {
+        let (Some(token), Some(delivery)) = (
+            self.cluster_registration_token.as_ref(),
+            self.config.agent_delivery(),
+        ) else {
+            return Ok(None);
+        };
+
+        if registered {
+            if delivery == AgentDelivery::ClusterResourceSet {
+                self.remove_resource_set(ctx).await?;
+            }
+            return Ok(Some(ClusterCondition::ready(
+                FLEET_AGENT_DELIVERED_CONDITION,
+                "AgentRegistered",
+            )));
+        }
+
+        let Some(registration) = self.agent_registration(ctx.clone(), token).await? else {
+            return Ok(Some(ClusterCondition::not_ready(
+                FLEET_AGENT_DELIVERED_CONDITION,
+                "Info",
+                "WaitingForRegistrationToken",
+                format!(
+                    "Waiting for Fleet to issue the registration token `{}`",
+                    token.name_any()
+                ),
+            )));
+        };
+
+        let reason = match delivery {
+            AgentDelivery::Kubeconfig => {
+                let Some(client) = self.downstream_client(ctx).await? else {
+                    return Ok(Some(ClusterCondition::not_ready(
+                        FLEET_AGENT_DELIVERED_CONDITION,
+                        "Warning",
+                        "KubeconfigSecretMissing",
+                        format!(
+                            "Kubeconfig secret for cluster `{}` is not found",
+                            self.cluster.name_any()
+                        ),
+                    )));
+                };
+
+                for (resource, manifest) in registration.manifests()? {
+                    let api = match manifest.namespace() {
+                        Some(ns) => {
+                            Api::<DynamicObject>::namespaced_with(client.clone(), &ns, &resource)
+                        }
+                        None => Api::all_with(client.clone(), &resource),
+                    };
+                    api.patch(
+                        &manifest.name_any(),
+                        &PatchParams::apply("addon-provider-fleet").force(),
+                        &Patch::Apply(&manifest),
+                    )
+                    .await
+                    .map_err(ClusterSyncError::AgentDeliveryError)?;
+                }
+
+                "AgentApplied"
+            }
+            AgentDelivery::ClusterResourceSet => {
+                self.apply_resource_set(ctx, &registration).await?;
+                "ClusterResourceSetApplied"
+            }
+        };
+
+        debug!(
+            "Delivered fleet agent registration to cluster {}",
+            self.cluster.name_any()
+        );
+        Ok(Some(ClusterCondition::ready(
+            FLEET_AGENT_DELIVERED_CONDITION,
+            reason,
+        )))
+    }
+
+    /// Create the `ClusterResourceSet` applying the agent manifests, and label the cluster
+    /// to be selected by it.
+    async fn apply_resource_set(
+        &self,
+        ctx: Arc<Context>,
+        registration: &AgentRegistration,
+    ) -> ClusterSyncResult<()> {
+        let name = self.cluster.name_any();
+        let ns = self.cluster.get_namespace();
+        if self.cluster.labels().get(CLUSTER_NAME_LABEL) != Some(&name) {
+            let patch = json!({
+                "metadata": {
+                    "labels": {
+                        CLUSTER_NAME_LABEL: name,
+                    }
+                }
+            });
+            Cluster::get_api(ctx.client.clone(), ns)
+                .patch_metadata(&name, &PatchParams::default(), &Patch::Merge(&patch))
+                .await
+                .map_err(ClusterSyncError::AgentDeliveryError)?;
+        }
+
+        let (secret, resource_set) =
+            registration.to_resource_set(&self.cluster, ctx.capi_version)?;
+        let pp = PatchParams::apply("addon-provider-fleet").force();
+        Secret::get_api(ctx.client.clone(), ns)
+            .patch(&secret.name_any(), &pp, &Patch::Apply(&secret))
+            .await
+            .map_err(ClusterSyncError::AgentDeliveryError)?;
+        Api::<DynamicObject>::namespaced_with(
+            ctx.client.clone(),
+            ns,
+            &resource_set_resource(ctx.capi_version),
+        )
+        .patch(&resource_set.name_any(), &pp, &Patch::Apply(&resource_set))
+        .await
+        .map_err(ClusterSyncError::AgentDeliveryError)?;
+
+        Ok(())
+    }
+
+    /// Remove the agent `ClusterResourceSet` and its secret holding the registration token,
+    /// once the agent has registered. Applied agent resources are kept in the cluster.
+    async fn remove_resource_set(&self, ctx: Arc<Context>) -> ClusterSyncResult<()> {
+        let name = resource_set_name(&self.cluster);
+        let ns = self.cluster.get_namespace();
+        delete_opt(
+            &Api::<DynamicObject>::namespaced_with(
+                ctx.client.clone(),
+                ns,
+                &resource_set_resource(ctx.capi_version),
+            ),
+            &name,
+        )
+        .await
+        .map_err(ClusterSyncError::DeleteError)?;
+        delete_opt(&Secret::get_api(ctx.client.clone(), ns), &name)
+            .await
+            .map_err(ClusterSyncError::DeleteError)
+    }
+
+    /// Remove the fleet finalizer, allowing the cluster to be deleted without fleet cleanup.
+    async fn remove_finalizer(&self, ctx: Arc<Context>) -> ClusterSyncResult<()> {
+        let api = Cluster::get_api(ctx.client.clone(), self.cluster.get_namespace());
+        let current = api
+            .get_metadata(&self.cluster.name_any())
+            .await
+            .map_err(ClusterSyncError::ClusterLookupError)?;
+        let finalizers: Vec<&String> = current
+            .finalizers()
+            .iter()
+            .filter(|f| *f != FLEET_FINALIZER)
+            .collect();
+
+        // The resource version guards the finalizers list from concurrent updates
+        let patch = json!({
+            "metadata": {
+                "resourceVersion": current.resource_version(),
+                "finalizers": finalizers,
+            }
+        });
+        api.patch_metadata(
+            &self.cluster.name_any(),
+            &PatchParams::default(),
+            &Patch::Merge(&patch),
+        )
+        .await
+        .map_err(ClusterSyncError::FinalizerPatchError)?;
+
+        Ok(())
     }
 }
 
 impl FleetBundle for FleetClusterBundle {
     #[allow(refining_impl_trait)]
     async fn sync(&mut self, ctx: Arc<Context>) -> ClusterSyncResult<Action> {
+        if let Some(adoption) = self.config.adoption().cloned() {
+            if !self.sync_adoption(ctx.clone(), &adoption).await? {
+                return Ok(Action::await_change());
+            }
+        }
+
+        // Owner references are removed ahead of the deletion, as `clusterctl move`
+        // removes the finalizers of paused clusters
+        if self.deletion_policy() == DeletionPolicy::Orphan {
+            self.orphan(ctx.clone()).await?;
+        }
+
+        let upgrading = self.upgrading(ctx.clone()).await;
+        if self.sync_pause(ctx.clone(), upgrading).await? {
+            debug!(
+                "Cluster {} is paused, skipping fleet objects update",
+                self.cluster.name_any()
+            );
+            // Control plane rollout progress is not watched
+            if upgrading {
+                return Ok(Action::requeue(UPGRADE_CHECK_INTERVAL));
+            }
+            return Ok(Action::await_change());
+        }
+
+        if let Err(e) = self.check_name_collision(ctx.clone()).await {
+            if let ClusterSyncError::NameCollision(..) = e {
+                let condition = ClusterCondition::not_ready(
+                    FLEET_IMPORTED_CONDITION,
+                    "Error",
+                    "NameCollision",
+                    e.to_string(),
+                );
+                self.patch_conditions(ctx.clone(), &[condition]).await?;
+            }
+            return Err(e);
+        }
+
+        self.report_agent_overrides(ctx.clone()).await?;
+        self.persist_client_id(ctx.clone()).await?;
+        let rotation = self.sync_scoped_kubeconfig(ctx.clone()).await?;
+        self.sync_agent_redeploy(ctx.clone()).await?;
+
         let cluster = &mut self.fleet;
 
-        if let Some(template) = self.template_sources.resolve(ctx.client.clone()).await {
+        if let Some(template) = self
+            .template_sources
+            .resolve(ctx.client.clone(), ctx.capi_version)
+            .await
+        {
             let template = serde_json::from_value(template)?;
             cluster.spec.template_values = Some(template);
         }
@@ -164,11 +1708,6 @@ impl FleetBundle for FleetClusterBundle {
             get_or_create(ctx.clone(), cluster).await?
         };
 
-        #[cfg(feature = "agent-initiated")]
-        if let Some(cluster_registration_token) = self.cluster_registration_token.as_ref() {
-            get_or_create(ctx.clone(), cluster_registration_token).await?;
-        }
-
         if let Some(group) = self.fleet_group.as_mut() {
             let cluster_name = self.fleet.name_any();
             if self.config.cluster_patch_enabled() {
@@ -196,10 +1735,62 @@ impl FleetBundle for FleetClusterBundle {
             self.fleet.get_namespace()
         );
 
-        Ok(Action::await_change())
+        let registered = self.agent_registered(ctx.clone()).await?;
+        let action = self
+            .sync_registration_token(ctx.clone(), registered)
+            .await?;
+
+        // Delivery failures are reported on the cluster before retrying
+        let (delivered, delivery_error) = match self.deliver_agent(ctx.clone(), registered).await {
+            Ok(condition) => (condition, None),
+            Err(e) => (
+                Some(ClusterCondition::not_ready(
+                    FLEET_AGENT_DELIVERED_CONDITION,
+                    "Error",
+                    "AgentDeliveryFailed",
+                    e.to_string(),
+                )),
+                Some(e),
+            ),
+        };
+
+        self.update_conditions(ctx, delivered).await?;
+
+        match (delivery_error, rotation) {
+            (Some(e), _) => Err(e),
+            (None, Some(rotation)) => Ok(Action::requeue(rotation)),
+            (None, None) => Ok(action),
+        }
     }
 
     async fn cleanup(&mut self, ctx: Arc<Context>) -> Result<Action, super::SyncError> {
+        ctx.metrics
+            .remove_bundle_deployments(self.cluster.get_namespace(), &self.cluster.name_any());
+
+        // Adopted Fleet clusters keep their name
+        if let Some(adoption) = self.config.adoption() {
+            if let Some(existing) = self.adoption_candidate(ctx.clone(), adoption).await? {
+                if self.owns(&existing) {
+                    self.fleet.metadata.name = Some(existing.name_any());
+                }
+            }
+        }
+
+        if self.deletion_policy() == DeletionPolicy::Orphan {
+            self.orphan(ctx).await?;
+            return Ok(Action::await_change());
+        }
+
+        // Paused clusters are being moved or maintained, the deletion is held until they resume.
+        // `clusterctl move` removes the finalizer, leaving the fleet objects in place.
+        if self.cluster.paused() {
+            return Err(ClusterSyncError::DeletionPaused.into());
+        }
+
+        if let Some(drain) = self.config.drain() {
+            self.drain(ctx.clone(), drain).await?;
+        }
+
         if let Some(mapping) = self.mapping.as_ref() {
             let ns = mapping.namespace();
             let other_clusters = ctx
@@ -212,7 +1803,7 @@ impl FleetBundle for FleetClusterBundle {
 
             let referencing_cluster = other_clusters.iter().find(|c| {
                 c.cluster_class_namespace() == ns.as_deref()
-                    && c.name_any() != self.fleet.name_any()
+                    && c.name_any() != self.cluster.name_any()
                     && c.metadata.deletion_timestamp.is_none()
             });
 
@@ -229,7 +1820,7 @@ impl FleetBundle for FleetClusterBundle {
         let other_clusters = Cluster::get_api(ctx.client.clone(), self.fleet.get_namespace())
             .list(
                 &ListParams::default()
-                    .fields(&format!("metadata.name!={}", self.fleet.name_any()))
+                    .fields(&format!("metadata.name!={}", self.cluster.name_any()))
                     .limit(1),
             )
             .await?;
@@ -273,12 +1864,50 @@ impl FleetController for Cluster {
             return Ok(None);
         }
 
+        let policies = cached_policies(&ctx.policies).await?;
+        let ns_labels = if policies.is_empty() {
+            Default::default()
+        } else {
+            Namespace::get_api(ctx.client.clone(), &())
+                .get_metadata_opt(&self.namespace().unwrap_or_default())
+                .await
+                .map_err(LabelCheckError::from)?
+                .map(|ns| ns.labels().clone())
+                .unwrap_or_default()
+        };
+        let policy = FleetAddonClusterPolicy::resolve(
+            &policies,
+            self,
+            &ns_labels,
+            &ctx.controller_namespace,
+        );
+
+        let fleet = match self.to_cluster(config.spec.cluster.as_ref(), policy) {
+            Ok(fleet) => fleet,
+            Err(e) => {
+                publish_event(
+                    &ctx,
+                    &Event {
+                        type_: EventType::Warning,
+                        reason: "InvalidNaming".into(),
+                        note: Some(e.to_string()),
+                        action: "Creating".into(),
+                        secondary: policy.map(|policy| policy.object_ref(&())),
+                    },
+                    &self.object_ref(&()),
+                )
+                .await
+                .map_err(BundleError::Event)?;
+                return Err(e.into());
+            }
+        };
+
         Ok(Some(FleetClusterBundle {
+            cluster: self.clone(),
             template_sources: TemplateSources::new(self),
-            fleet: self.to_cluster(config.spec.cluster.as_ref()),
+            fleet,
             fleet_group: self.to_group(config.spec.cluster.as_ref()),
             mapping: self.to_bundle_ns_mapping(config.spec.cluster.as_ref()),
-            #[cfg(feature = "agent-initiated")]
             cluster_registration_token: self
                 .to_cluster_registration_token(config.spec.cluster.as_ref()),
             config,
@@ -290,13 +1919,19 @@ impl FleetController for Cluster {
 impl Cluster {
     #[must_use]
     pub fn cluster_ready(&self) -> Option<&Self> {
-        let status = self.status.clone()?;
-        let cp_ready = status.control_plane_ready.filter(|&ready| ready);
-        let ready_condition = status.conditions?.iter().find_map(|c| {
-            (c.type_ == CONTROLPLANE_INITIALIZED_CONDITION && c.status == "True").then_some(true)
+        let status = self.status.as_ref()?;
+        let cp_ready = status.control_plane_ready.unwrap_or_default();
+        let cp_initialized = status
+            .initialization
+            .as_ref()
+            .and_then(|i| i.control_plane_initialized)
+            .unwrap_or_default();
+        let ready_condition = status.conditions.iter().flatten().any(|c| {
+            (c.type_ == CONTROLPLANE_INITIALIZED_CONDITION || c.type_ == AVAILABLE_CONDITION)
+                && c.status == "True"
         });
 
-        ready_condition.or(cp_ready).map(|_| self)
+        (cp_ready || cp_initialized || ready_condition).then_some(self)
     }
 
     /// Adds a dynamic watcher for a specific namespace.
@@ -322,4 +1957,193 @@ impl Cluster {
 
         Ok(Action::await_change())
     }
+    /// Un-import a cluster which no longer matches the import selectors, when the
+    /// un-import policy is set to `remove`. Clusters out of the import scope are not
+    /// watched by the main cluster controller, so this runs on cluster metadata instead.
+    pub async fn reconcile_unimport(
+        cluster: Arc<PartialObjectMeta<Cluster>>,
+        ctx: Arc<Context>,
+    ) -> crate::Result<Action> {
+        let imported = cluster.finalizers().iter().any(|f| f == FLEET_FINALIZER);
+        if !imported || cluster.metadata.deletion_timestamp.is_some() {
+            return Ok(Action::await_change());
+        }
+
+        let config = fetch_config(ctx.client.clone())
+            .await
+            .map_err(BundleError::from)?;
+        if !config.unimport_remove_enabled() {
+            return Ok(Action::await_change());
+        }
+
+        let ns_labels = Namespace::get_api(ctx.client.clone(), &())
+            .get_metadata_opt(&cluster.namespace().unwrap_or_default())
+            .await?
+            .map(|ns| ns.labels().clone())
+            .unwrap_or_default();
+        let in_scope = config
+            .cluster_in_scope(cluster.labels(), &ns_labels)
+            .map_err(|e| SyncError::from(LabelCheckError::from(e)))?;
+        if in_scope {
+            return Ok(Action::await_change());
+        }
+
+        let Some(cluster) = Cluster::get_api(ctx.client.clone(), cluster.get_namespace())
+            .get_opt(&cluster.name_any())
+            .await?
+        else {
+            return Ok(Action::await_change());
+        };
+
+        // Paused clusters are being moved or maintained, fleet objects are left in place
+        if cluster.paused() {
+            return Ok(Action::await_change());
+        }
+
+        match cluster.to_bundle(ctx.clone()).await? {
+            Some(mut bundle) => Ok(bundle.unimport(ctx).await?),
+            None => Ok(Action::await_change()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use http::{Method, Request, Response};
+    use kube::{Client, ResourceExt as _, client::Body, runtime::reflector};
+    use serde_json::{Value, json};
+    use tokio::{sync::Barrier, task::JoinHandle};
+
+    use super::FleetClusterBundle;
+    use crate::{
+        api::{
+            capi_cluster::{CLUSTER_NAME_LABEL, Cluster},
+            capi_contract::CapiVersion,
+            fleet_addon_config::{AdoptionConfig, AdoptionPolicy, FleetAddonConfig},
+            fleet_cluster,
+        },
+        controllers::{cluster::TemplateSources, controller::Context},
+        metrics::Metrics,
+        multi_dispatcher::{BroadcastStream, MultiDispatcher},
+    };
+
+    fn context(client: Client) -> Arc<Context> {
+        Arc::new(Context {
+            client,
+            diagnostics: Arc::default(),
+            metrics: Metrics::default(),
+            dispatcher: MultiDispatcher::new(1),
+            stream: BroadcastStream::new(Arc::default()),
+            version: 0,
+            barrier: Arc::new(Barrier::new(1)),
+            fleet_namespace: "fleet-system".into(),
+            controller_namespace: "caapf-system".into(),
+            policies: reflector::store().0,
+            capi_version: CapiVersion::default(),
+        })
+    }
+
+    fn bundle() -> FleetClusterBundle {
+        let cluster: Cluster = serde_json::from_value(json!({
+            "apiVersion": "cluster.x-k8s.io/v1beta1",
+            "kind": "Cluster",
+            "metadata": {"name": "test", "namespace": "default", "uid": "1234"},
+            "spec": {},
+        }))
+        .unwrap();
+
+        let mut fleet = fleet_cluster::Cluster::default();
+        fleet.metadata.name = Some("test".into());
+        fleet.metadata.namespace = Some("default".into());
+
+        FleetClusterBundle {
+            namespace: cluster.to_namespace(),
+            template_sources: TemplateSources::new(&cluster),
+            cluster,
+            fleet,
+            fleet_group: None,
+            mapping: None,
+            cluster_registration_token: None,
+            config: FleetAddonConfig::default(),
+        }
+    }
+
+    /// Served requests with their JSON bodies.
+    type Served = Vec<(Method, String, Option<Value>)>;
+
+    /// Client serving the Fleet cluster metadata list, recording the requests and their bodies.
+    fn adoption_client(fleet: Value) -> (Client, JoinHandle<Served>) {
+        let (service, mut handle) = tower_test::mock::pair::<Request<Body>, Response<Body>>();
+        let server = tokio::spawn(async move {
+            let mut served = vec![];
+            while let Some((request, send)) = handle.next_request().await {
+                let method = request.method().clone();
+                let path = request.uri().path().to_string();
+                let body = request.into_body().collect_bytes().await.unwrap();
+                let response = if method == Method::GET {
+                    json!({
+                        "apiVersion": "meta.k8s.io/v1",
+                        "kind": "PartialObjectMetadataList",
+                        "metadata": {},
+                        "items": [fleet],
+                    })
+                } else {
+                    fleet.clone()
+                };
+                send.send_response(
+                    Response::builder()
+                        .body(Body::from(serde_json::to_vec(&response).unwrap()))
+                        .unwrap(),
+                );
+                served.push((method, path, serde_json::from_slice(&body).ok()));
+            }
+            served
+        });
+
+        (Client::new(service, "default"), server)
+    }
+
+    #[tokio::test]
+    async fn test_sync_adoption_owned_without_label() {
+        // Imported before the cluster name label was set, under a different naming strategy
+        let (client, server) = adoption_client(json!({
+            "apiVersion": "meta.k8s.io/v1",
+            "kind": "PartialObjectMetadata",
+            "metadata": {
+                "name": "prefix-test",
+                "namespace": "default",
+                "ownerReferences": [{
+                    "apiVersion": "cluster.x-k8s.io/v1beta1",
+                    "kind": "Cluster",
+                    "name": "test",
+                    "uid": "1234",
+                }],
+            },
+        }));
+
+        let mut bundle = bundle();
+        let adoption = AdoptionConfig {
+            policy: AdoptionPolicy::Fail,
+            ..Default::default()
+        };
+        let imported = bundle
+            .sync_adoption(context(client), &adoption)
+            .await
+            .unwrap();
+
+        assert!(imported);
+        assert_eq!(bundle.fleet.name_any(), "prefix-test");
+
+        let served = server.await.unwrap();
+        assert_eq!(served.len(), 2, "{served:?}");
+        let (method, path, patch) = &served[1];
+        assert_eq!(*method, Method::PATCH);
+        assert!(path.ends_with("/clusters/prefix-test"), "{path}");
+        assert_eq!(
+            patch.as_ref().unwrap()["metadata"],
+            json!({"labels": {CLUSTER_NAME_LABEL: "test"}})
+        );
+    }
 }
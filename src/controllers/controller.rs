@@ -1,4 +1,6 @@
+use crate::api::capi_contract::CapiVersion;
 use crate::api::comparable::ResourceDiff;
+use crate::api::fleet_addon_cluster_policy::FleetAddonClusterPolicy;
 use crate::api::fleet_addon_config::FleetAddonConfig;
 use crate::controllers::PatchError;
 use crate::metrics::Diagnostics;
@@ -8,14 +10,16 @@ use chrono::Utc;
 
 use futures::Stream;
 use futures::stream::SelectAll;
+use k8s_openapi::api::core::v1::ObjectReference;
 use k8s_openapi::{ClusterResourceScope, NamespaceResourceScope};
 
-use kube::api::{DynamicObject, Patch, PatchParams, PostParams};
+use kube::api::{DeleteParams, DynamicObject, Patch, PatchParams, PostParams};
 
 use kube::runtime::events::{Event, EventType};
+use kube::runtime::reflector::Store;
 use kube::runtime::{finalizer, watcher};
 
-use kube::{api::Api, client::Client, runtime::controller::Action};
+use kube::{ResourceExt as _, api::Api, client::Client, runtime::controller::Action};
 
 use serde::Serialize;
 use serde::de::DeserializeOwned;
@@ -54,6 +58,14 @@ pub struct Context {
     pub version: u32,
     // Controller readiness barrier
     pub barrier: Arc<Barrier>,
+    // Fleet controller installation namespace
+    pub fleet_namespace: String,
+    // Namespace the controller is running in
+    pub controller_namespace: String,
+    // Cluster policies, reflected on every replica
+    pub policies: Store<FleetAddonClusterPolicy>,
+    // Cluster API version discovered at startup
+    pub capi_version: CapiVersion,
 }
 
 #[instrument(skip_all, fields(name = res.name_any(), namespace = res.namespace(), api_version = typed_gvk::<R>(&()).api_version(), kind = R::kind(&()).to_string()), err)]
@@ -260,6 +272,75 @@ where
     }
 }
 
+/// Publish an event for the referenced object, ignoring forbidden errors on namespace deletion.
+pub(crate) async fn publish_event(
+    ctx: &Context,
+    event: &Event,
+    reference: &ObjectReference,
+) -> Result<(), kube::Error> {
+    match ctx
+        .diagnostics
+        .read()
+        .await
+        .recorder(ctx.client.clone())
+        .publish(event, reference)
+        .await
+    {
+        // Ignore forbidden errors on namespace deletion
+        Err(kube::Error::Api(e)) if &e.reason == "Forbidden" => Ok(()),
+        e => e,
+    }
+}
+
+/// Delete the object, ignoring objects which are already removed.
+pub(crate) async fn delete_opt<K>(api: &Api<K>, name: &str) -> Result<(), kube::Error>
+where
+    K: Clone + DeserializeOwned + Debug,
+{
+    match api.delete(name, &DeleteParams::default()).await {
+        Ok(_) => Ok(()),
+        Err(kube::Error::Api(e)) if e.code == 404 => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Remove the owner reference with the uid from the object, if present.
+pub(crate) async fn remove_owner<K>(api: &Api<K>, name: &str, uid: &str) -> Result<(), kube::Error>
+where
+    K: kube::Resource + Clone + DeserializeOwned + Debug,
+{
+    let Some(current) = api.get_metadata_opt(name).await? else {
+        return Ok(());
+    };
+
+    if current
+        .owner_references()
+        .iter()
+        .all(|owner| owner.uid != uid)
+    {
+        return Ok(());
+    }
+
+    let owner_references: Vec<_> = current
+        .owner_references()
+        .iter()
+        .filter(|owner| owner.uid != uid)
+        .collect();
+
+    // The resource version guards the owner references list from concurrent updates
+    let patch = serde_json::json!({
+        "metadata": {
+            "resourceVersion": current.resource_version(),
+            "ownerReferences": owner_references,
+        }
+    });
+    api.patch_metadata(name, &PatchParams::default(), &Patch::Merge(&patch))
+        .await?;
+
+    info!("Removed owner reference {uid} from {name}");
+    Ok(())
+}
+
 pub(crate) async fn fetch_config(client: Client) -> ConfigFetchResult<FleetAddonConfig> {
     Ok(Api::all(client)
         .get_opt("fleet-addon-config")
@@ -267,6 +348,14 @@ pub(crate) async fn fetch_config(client: Client) -> ConfigFetchResult<FleetAddon
         .unwrap_or_default())
 }
 
+/// Cluster policies from the reflector store, once the initial list is received.
+pub(crate) async fn cached_policies(
+    store: &Store<FleetAddonClusterPolicy>,
+) -> ConfigFetchResult<Vec<Arc<FleetAddonClusterPolicy>>> {
+    store.wait_until_ready().await?;
+    Ok(store.state())
+}
+
 pub(crate) trait FleetBundle {
     async fn sync(&mut self, ctx: Arc<Context>) -> Result<Action, impl Into<SyncError>>;
     #[allow(clippy::unused_async)]
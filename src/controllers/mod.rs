@@ -1,5 +1,8 @@
+use kube::runtime::reflector::store::WriterDropped;
 use thiserror::Error;
 
+use crate::api::fleet_addon_config::NamingError;
+
 #[derive(Error, Debug)]
 pub enum SyncError {
     #[error("{0}")]
@@ -42,6 +45,81 @@ pub enum ClusterSyncError {
 
     #[error("Cluster json encoding error: {0}")]
     ClusterEncodeError(#[from] serde_json::Error),
+
+    #[error("Cluster lookup error: {0}")]
+    ClusterLookupError(#[source] kube::Error),
+
+    #[error("Fleet cluster name `{0}` is already used by cluster `{1}`")]
+    NameCollision(String, String),
+
+    #[error("Diagnostics error: {0}")]
+    Event(#[source] kube::Error),
+
+    #[error("Fleet cluster `{0}` already exists, and the adoption policy is `fail`")]
+    AdoptionRefused(String),
+
+    #[error("Fleet cluster adoption error: {0}")]
+    AdoptionPatchError(#[source] kube::Error),
+
+    #[error("Cluster conditions update error: {0}")]
+    ConditionsPatchError(#[source] kube::Error),
+
+    #[error("BundleDeployment lookup error: {0}")]
+    BundleDeploymentLookupError(#[source] kube::Error),
+
+    #[error("Cluster pause update error: {0}")]
+    PausePatchError(#[source] kube::Error),
+
+    #[error("Fleet agent redeploy error: {0}")]
+    RedeployPatchError(#[source] kube::Error),
+
+    #[error("Fleet object delete error: {0}")]
+    DeleteError(#[source] kube::Error),
+
+    #[error("Cluster drain update error: {0}")]
+    DrainPatchError(#[source] kube::Error),
+
+    #[error("Waiting for {0} bundle deployments to be removed from the cluster")]
+    DrainPending(usize),
+
+    #[error("Cluster is paused, the deletion is held until it is resumed")]
+    DeletionPaused,
+
+    #[error("Owner reference removal error: {0}")]
+    OrphanPatchError(#[source] kube::Error),
+
+    #[error("Cluster finalizer removal error: {0}")]
+    FinalizerPatchError(#[source] kube::Error),
+
+    #[error("Downstream cluster kubeconfig error: {0}")]
+    KubeconfigError(#[from] kube::config::KubeconfigError),
+
+    #[error("Fleet agent removal error: {0}")]
+    AgentRemovalError(#[source] kube::Error),
+
+    #[error("Cluster registration token error: {0}")]
+    RegistrationTokenError(#[source] kube::Error),
+
+    #[error("Cluster client ID update error: {0}")]
+    ClientIdPatchError(#[source] kube::Error),
+
+    #[error("Downstream cluster client error: {0}")]
+    DownstreamClientError(#[source] kube::Error),
+
+    #[error("Fleet agent delivery error: {0}")]
+    AgentDeliveryError(#[source] kube::Error),
+
+    #[error("YAML encode error: {0}")]
+    YamlError(#[from] serde_yaml::Error),
+
+    #[error("Fleet agent image is not set in the fleet-controller config")]
+    AgentImageMissing,
+
+    #[error("{0}")]
+    AgentManifestError(#[from] crate::api::fleet_agent::AgentManifestError),
+
+    #[error("Scoped kubeconfig error: {0}")]
+    ScopedKubeconfigError(#[source] kube::Error),
 }
 
 pub type GroupSyncResult<T, E = GroupSyncError> = std::result::Result<T, E>;
@@ -109,6 +187,12 @@ pub enum BundleError {
 
     #[error("BundleNamespaceMapping creating error: {0}")]
     Mapping(#[from] BundleMappingError),
+
+    #[error("Fleet cluster naming error: {0}")]
+    Naming(#[from] NamingError),
+
+    #[error("Diagnostics error: {0}")]
+    Event(#[source] kube::Error),
 }
 
 #[derive(Error, Debug)]
@@ -123,6 +207,9 @@ pub type ConfigFetchResult<T> = std::result::Result<T, ConfigFetchError>;
 pub enum ConfigFetchError {
     #[error("Config lookup error: {0}")]
     Lookup(#[from] kube::Error),
+
+    #[error("Cluster policy store is closed: {0}")]
+    PolicyStore(#[from] WriterDropped),
 }
 
 pub mod addon_config;
@@ -1,4 +1,6 @@
-use ::controller::api::fleet_addon_config::FleetAddonConfig;
+use ::controller::api::{
+    fleet_addon_cluster_policy::FleetAddonClusterPolicy, fleet_addon_config::FleetAddonConfig,
+};
 use kube::CustomResourceExt;
 
 fn main() {
@@ -6,4 +8,9 @@ fn main() {
         "{}",
         serde_yaml::to_string(&FleetAddonConfig::crd()).unwrap()
     );
+    println!("---");
+    print!(
+        "{}",
+        serde_yaml::to_string(&FleetAddonClusterPolicy::crd()).unwrap()
+    );
 }
@@ -0,0 +1,79 @@
+use std::{fs, io::Write as _, path::PathBuf};
+
+use ::controller::{
+    api::capi_contract::CapiVersion,
+    archive::{self, FleetArchive},
+};
+use clap::{Parser, Subcommand};
+use kube::Client;
+use tracing_subscriber::EnvFilter;
+
+/// Move the Fleet registrations of CAPI clusters to another management cluster,
+/// alongside `clusterctl move`
+#[derive(Parser, Debug)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Export the Fleet objects of the paused CAPI clusters from the source management cluster.
+    /// Once the archive is written, owner references to the CAPI clusters are removed from the
+    /// exported objects, so they are not deleted by `clusterctl move`
+    Export {
+        /// Namespace of the CAPI clusters. Defaults to all namespaces
+        #[arg(long, short)]
+        namespace: Option<String>,
+
+        /// Archive file. Defaults to the standard output
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+
+    /// Restore the Fleet objects on the target management cluster
+    Restore {
+        /// Archive file
+        #[arg(long, short)]
+        input: PathBuf,
+    },
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    // Logs are written to stderr, keeping the exported archive on stdout intact
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .init();
+
+    let args = Args::parse();
+    let client = Client::try_default().await?;
+    CapiVersion::discover(&client).await.select();
+
+    match args.command {
+        Command::Export { namespace, output } => {
+            let exported = archive::export(client.clone(), namespace.as_deref()).await?;
+            let data = serde_yaml::to_string(&exported)?;
+            match output {
+                Some(path) => fs::write(path, data)?,
+                None => {
+                    let mut stdout = std::io::stdout().lock();
+                    stdout.write_all(data.as_bytes())?;
+                    stdout.flush()?;
+                }
+            }
+
+            // Source objects are only modified once the archive is stored
+            archive::release(client, namespace.as_deref(), &exported).await?;
+        }
+        Command::Restore { input } => {
+            let archive: FleetArchive = serde_yaml::from_str(&fs::read_to_string(input)?)?;
+            archive::restore(client, archive).await?;
+        }
+    }
+
+    Ok(())
+}
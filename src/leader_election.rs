@@ -0,0 +1,398 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::Utc;
+use k8s_openapi::api::coordination::v1::{Lease, LeaseSpec};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::MicroTime;
+use kube::api::{ObjectMeta, PostParams};
+use kube::{Api, Client, ResourceExt as _};
+use prometheus::IntGauge;
+use rand::distr::{Alphanumeric, SampleString as _};
+use thiserror::Error;
+use tokio::signal::unix::{SignalKind, signal};
+use tokio::time::{Instant, sleep};
+use tracing::{info, warn};
+
+use crate::controller::{Flags, controller_namespace};
+
+pub type LeaderElectionResult<T, E = LeaderElectionError> = std::result::Result<T, E>;
+
+#[derive(Error, Debug)]
+pub enum LeaderElectionError {
+    #[error("Lease lookup error: {0}")]
+    Lookup(#[source] kube::Error),
+
+    #[error("Lease create error: {0}")]
+    Create(#[source] kube::Error),
+
+    #[error("Lease update error: {0}")]
+    Update(#[source] kube::Error),
+
+    #[error("Leadership lost for lease {0}")]
+    Lost(String),
+}
+
+/// `LeaderElection` maintains a `coordination.k8s.io/v1` Lease, allowing only one
+/// controller replica to run reconcilers at a time.
+pub struct LeaderElection {
+    api: Api<Lease>,
+    name: String,
+    identity: String,
+    lease_duration: Duration,
+    renew_deadline: Duration,
+    retry_period: Duration,
+    leader: IntGauge,
+    /// Resource version of the last observed lease, with the local time it was observed.
+    /// Expiration is measured with the local clock, as the holder clock may be skewed.
+    observed: Mutex<Option<(String, Instant)>>,
+}
+
+impl LeaderElection {
+    #[must_use]
+    pub fn new(client: Client, flags: &Flags, leader: IntGauge) -> Self {
+        let namespace = flags
+            .leader_election_namespace
+            .clone()
+            .unwrap_or_else(|| controller_namespace(&client));
+        let identity = std::env::var("POD_NAME")
+            .or_else(|_| std::env::var("HOSTNAME"))
+            .unwrap_or_else(|_| Alphanumeric.sample_string(&mut rand::rng(), 16));
+
+        Self {
+            api: Api::namespaced(client, &namespace),
+            name: flags.leader_election_id.clone(),
+            identity,
+            lease_duration: Duration::from_secs(flags.leader_election_lease_duration),
+            renew_deadline: Duration::from_secs(flags.leader_election_renew_deadline),
+            retry_period: Duration::from_secs(flags.leader_election_retry_period),
+            leader,
+            observed: Mutex::default(),
+        }
+    }
+
+    /// Blocks until the lease is acquired by this instance.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the lease can't be read or written.
+    pub async fn acquire(&self) -> LeaderElectionResult<()> {
+        info!(
+            "Attempting to acquire leader lease {} as {}",
+            self.name, self.identity
+        );
+        while !self.try_acquire_or_renew().await? {
+            sleep(self.retry_period).await;
+        }
+
+        info!("Acquired leader lease {}", self.name);
+        self.leader.set(1);
+        Ok(())
+    }
+
+    /// Keeps renewing the held lease. Only returns when the leadership is lost.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LeaderElectionError::Lost` once the lease is taken over or could not be
+    /// renewed within the renew deadline.
+    pub async fn renew(&self) -> LeaderElectionResult<()> {
+        let mut last_renew = Instant::now();
+        loop {
+            sleep(self.retry_period).await;
+            match self.try_acquire_or_renew().await {
+                Ok(true) => last_renew = Instant::now(),
+                Ok(false) => break,
+                Err(e) if last_renew.elapsed() < self.renew_deadline => {
+                    warn!("Failed to renew leader lease {}: {e}", self.name);
+                }
+                Err(e) => {
+                    warn!("Failed to renew leader lease {}: {e}", self.name);
+                    break;
+                }
+            }
+        }
+
+        self.leader.set(0);
+        Err(LeaderElectionError::Lost(self.name.clone()))
+    }
+
+    /// Releases the lease if it is still held, so a standby can take over immediately.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the lease can't be read or written.
+    pub async fn release(&self) -> LeaderElectionResult<()> {
+        self.leader.set(0);
+        let Some(mut lease) = self
+            .api
+            .get_opt(&self.name)
+            .await
+            .map_err(LeaderElectionError::Lookup)?
+        else {
+            return Ok(());
+        };
+
+        let spec = lease.spec.get_or_insert_default();
+        if spec.holder_identity.as_ref() != Some(&self.identity) {
+            return Ok(());
+        }
+
+        spec.holder_identity = None;
+        spec.acquire_time = None;
+        spec.renew_time = Some(MicroTime(Utc::now()));
+        spec.lease_duration_seconds = Some(1);
+
+        self.api
+            .replace(&self.name, &PostParams::default(), &lease)
+            .await
+            .map_err(LeaderElectionError::Update)?;
+
+        info!("Released leader lease {}", self.name);
+        Ok(())
+    }
+
+    async fn try_acquire_or_renew(&self) -> LeaderElectionResult<bool> {
+        let now = Utc::now();
+        let lease_duration_seconds = i32::try_from(self.lease_duration.as_secs()).ok();
+
+        let Some(existing) = self
+            .api
+            .get_opt(&self.name)
+            .await
+            .map_err(LeaderElectionError::Lookup)?
+        else {
+            let lease = Lease {
+                metadata: ObjectMeta {
+                    name: Some(self.name.clone()),
+                    ..Default::default()
+                },
+                spec: Some(LeaseSpec {
+                    holder_identity: Some(self.identity.clone()),
+                    acquire_time: Some(MicroTime(now)),
+                    renew_time: Some(MicroTime(now)),
+                    lease_duration_seconds,
+                    lease_transitions: Some(0),
+                    ..Default::default()
+                }),
+            };
+
+            return match self.api.create(&PostParams::default(), &lease).await {
+                Ok(_) => Ok(true),
+                Err(kube::Error::Api(e)) if e.code == 409 => Ok(false),
+                Err(e) => Err(LeaderElectionError::Create(e)),
+            };
+        };
+
+        let spec = existing.spec.clone().unwrap_or_default();
+        let held = spec.holder_identity.as_ref() == Some(&self.identity);
+        let vacant = spec.holder_identity.as_ref().is_none_or(String::is_empty);
+        let duration = u64::try_from(spec.lease_duration_seconds.unwrap_or_default())
+            .map(Duration::from_secs)
+            .unwrap_or_default();
+        let observed = self.observe(existing.resource_version().unwrap_or_default());
+        let expired = observed.elapsed() >= duration;
+
+        if !held && !vacant && !expired {
+            return Ok(false);
+        }
+
+        let lease = Lease {
+            metadata: existing.metadata,
+            spec: Some(LeaseSpec {
+                holder_identity: Some(self.identity.clone()),
+                acquire_time: if held {
+                    spec.acquire_time
+                } else {
+                    Some(MicroTime(now))
+                },
+                renew_time: Some(MicroTime(now)),
+                lease_duration_seconds,
+                lease_transitions: if held {
+                    spec.lease_transitions
+                } else {
+                    Some(spec.lease_transitions.unwrap_or_default() + 1)
+                },
+                ..spec
+            }),
+        };
+
+        // Replace carries the observed resourceVersion, so concurrent takeovers conflict
+        match self
+            .api
+            .replace(&self.name, &PostParams::default(), &lease)
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(kube::Error::Api(e)) if e.code == 409 => Ok(false),
+            Err(e) => Err(LeaderElectionError::Update(e)),
+        }
+    }
+
+    /// Local time the lease was first observed in this version. Every renewal by the
+    /// holder updates the lease, restarting the expiration.
+    fn observe(&self, version: String) -> Instant {
+        let mut observed = self
+            .observed
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        match observed.as_ref() {
+            Some((observed_version, at)) if *observed_version == version => *at,
+            _ => observed.insert((version, Instant::now())).1,
+        }
+    }
+}
+
+/// Resolves once the process receives SIGTERM or SIGINT.
+///
+/// # Panics
+///
+/// Panics if the signal handler cannot be installed.
+pub async fn shutdown_signal() {
+    let mut terminate = signal(SignalKind::terminate()).expect("install SIGTERM handler");
+    tokio::select! {
+        _ = terminate.recv() => {},
+        _ = tokio::signal::ctrl_c() => {},
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Mutex, time::Duration};
+
+    use http::{Method, Request, Response, StatusCode};
+    use kube::{Api, Client, client::Body};
+    use prometheus::IntGauge;
+    use serde_json::{Value, json};
+    use tokio::{task::JoinHandle, time::Instant};
+
+    use super::LeaderElection;
+
+    fn lease_election(client: Client) -> LeaderElection {
+        LeaderElection {
+            api: Api::namespaced(client, "default"),
+            name: "lease".into(),
+            identity: "me".into(),
+            lease_duration: Duration::from_secs(15),
+            renew_deadline: Duration::from_secs(10),
+            retry_period: Duration::from_secs(2),
+            leader: IntGauge::new("leader", "leader").unwrap(),
+            observed: Mutex::default(),
+        }
+    }
+
+    fn lease(holder: &str) -> Value {
+        json!({
+            "apiVersion": "coordination.k8s.io/v1",
+            "kind": "Lease",
+            "metadata": {"name": "lease", "namespace": "default", "resourceVersion": "1"},
+            "spec": {
+                "holderIdentity": holder,
+                // Renewed long ago according to a skewed holder clock
+                "renewTime": "2020-01-01T00:00:00.000000Z",
+                "leaseDurationSeconds": 15,
+            },
+        })
+    }
+
+    /// Client serving the lease from `get`, recording the requests methods.
+    fn lease_client(get: Option<Value>) -> (Client, JoinHandle<Vec<Method>>) {
+        let (service, mut handle) = tower_test::mock::pair::<Request<Body>, Response<Body>>();
+        let server = tokio::spawn(async move {
+            let mut served = vec![];
+            while let Some((request, send)) = handle.next_request().await {
+                let (status, body) = match &get {
+                    _ if *request.method() != Method::GET => (StatusCode::OK, lease("me")),
+                    Some(existing) => (StatusCode::OK, existing.clone()),
+                    None => (
+                        StatusCode::NOT_FOUND,
+                        json!({
+                            "apiVersion": "v1",
+                            "kind": "Status",
+                            "status": "Failure",
+                            "reason": "NotFound",
+                            "code": 404,
+                        }),
+                    ),
+                };
+                send.send_response(
+                    Response::builder()
+                        .status(status)
+                        .body(Body::from(serde_json::to_vec(&body).unwrap()))
+                        .unwrap(),
+                );
+                served.push(request.method().clone());
+            }
+            served
+        });
+
+        (Client::new(service, "default"), server)
+    }
+
+    #[tokio::test]
+    async fn test_acquire() {
+        let (client, server) = lease_client(None);
+        let election = lease_election(client);
+        election.acquire().await.unwrap();
+        assert_eq!(election.leader.get(), 1);
+        drop(election);
+
+        assert_eq!(server.await.unwrap(), [Method::GET, Method::POST]);
+    }
+
+    #[tokio::test]
+    async fn test_renew() {
+        let (client, server) = lease_client(Some(lease("me")));
+        let election = lease_election(client);
+        assert!(election.try_acquire_or_renew().await.unwrap());
+        drop(election);
+
+        assert_eq!(server.await.unwrap(), [Method::GET, Method::PUT]);
+    }
+
+    #[tokio::test]
+    async fn test_takeover() {
+        let (client, server) = lease_client(Some(lease("other")));
+        let election = lease_election(client);
+
+        // The holder renew time is ignored, the lease is valid until it stays unchanged
+        // for the lease duration, measured locally
+        assert!(!election.try_acquire_or_renew().await.unwrap());
+        assert!(!election.try_acquire_or_renew().await.unwrap());
+
+        let expired = Instant::now().checked_sub(Duration::from_secs(15)).unwrap();
+        *election.observed.lock().unwrap() = Some(("1".into(), expired));
+        assert!(election.try_acquire_or_renew().await.unwrap());
+
+        // A renewal by the holder restarts the expiration
+        *election.observed.lock().unwrap() = Some(("0".into(), expired));
+        assert!(!election.try_acquire_or_renew().await.unwrap());
+        drop(election);
+
+        assert_eq!(
+            server.await.unwrap(),
+            [
+                Method::GET,
+                Method::GET,
+                Method::GET,
+                Method::PUT,
+                Method::GET
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_release() {
+        let (client, server) = lease_client(Some(lease("me")));
+        let election = lease_election(client);
+        election.release().await.unwrap();
+        drop(election);
+        assert_eq!(server.await.unwrap(), [Method::GET, Method::PUT]);
+
+        // A lease held by another replica is left untouched
+        let (client, server) = lease_client(Some(lease("other")));
+        let election = lease_election(client);
+        election.release().await.unwrap();
+        drop(election);
+        assert_eq!(server.await.unwrap(), [Method::GET]);
+    }
+}
@@ -70,6 +70,15 @@ pub mod predicates;
 /// Log and trace integrations
 pub mod telemetry;
 
+/// Lease based leader election
+pub mod leader_election;
+
+/// Admission webhooks
+pub mod webhook;
+
+/// Fleet registration export and restore for `clusterctl move`
+pub mod archive;
+
 /// Metrics
 mod metrics;
 pub use metrics::Metrics;
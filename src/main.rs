@@ -1,9 +1,15 @@
 use actix_web::{
     App, HttpRequest, HttpResponse, HttpServer, Responder, get, middleware, web::Data,
 };
-pub use controller::{self, State, telemetry};
-use kube::Client;
+use controller::api::capi_contract::CapiVersion;
+pub use controller::{self, State, leader_election, telemetry, webhook};
+use kube::{Client, runtime::reflector};
+use leader_election::{LeaderElection, shutdown_signal};
 use prometheus::{Encoder, TextEncoder};
+use webhook::{
+    certificate::{CertificateManager, CertificateResolver},
+    hooks,
+};
 
 #[get("/metrics")]
 async fn metrics(c: Data<State>, _req: HttpRequest) -> impl Responder {
@@ -25,6 +31,42 @@ async fn index(c: Data<State>, _req: HttpRequest) -> impl Responder {
     HttpResponse::Ok().json(&d)
 }
 
+/// Runs the reconcilers. With leader election enabled, the reconcilers are started
+/// only after the lease is acquired, while standby replicas keep serving the web server.
+async fn run_controllers(state: State, client: Client) -> anyhow::Result<()> {
+    let election = state
+        .flags
+        .leader_elect
+        .then(|| LeaderElection::new(client, &state.flags, state.leader_metric()));
+
+    if let Some(election) = election.as_ref() {
+        tokio::select! {
+            res = election.acquire() => res?,
+            () = shutdown_signal() => return Ok(()),
+        }
+    }
+
+    let controllers = async {
+        tokio::join!(
+            controller::run_cluster_controller(state.clone()),
+            controller::run_cluster_class_controller(state.clone()),
+            controller::run_fleet_addon_config_controller(state.clone()),
+        );
+    };
+
+    match election {
+        Some(election) => {
+            tokio::select! {
+                () = controllers => election.release().await?,
+                res = election.renew() => res?,
+            }
+        }
+        None => controllers.await,
+    }
+
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     telemetry::init();
@@ -34,7 +76,8 @@ async fn main() -> anyhow::Result<()> {
         .expect("failed to create kube Client");
 
     // Init k8s controller state
-    let state = State::new(
+    let (policies, policy_writer) = reflector::store();
+    let mut state = State::new(
         client
             .apiserver_version()
             .await
@@ -42,36 +85,83 @@ async fn main() -> anyhow::Result<()> {
             .minor
             .parse()
             .expect("version parse successfully"),
+        policies,
     );
 
     if state.flags.helm_install {
         let helm_install_controller = controller::run_fleet_helm_controller(state.clone());
         tokio::join!(helm_install_controller);
     } else {
-        let fleet_config_controller = controller::run_fleet_addon_config_controller(state.clone());
-        let cluster_controller = controller::run_cluster_controller(state.clone());
-        let cluster_class_controller = controller::run_cluster_class_controller(state.clone());
+        // Cluster and ClusterClass resources are served in the most recent available version
+        state.capi_version = CapiVersion::discover(&client).await;
+        state.capi_version.select();
+        tokio::spawn(controller::run_policy_reflector(policy_writer));
+        let controllers = run_controllers(state.clone(), client.clone());
+
+        // Provision the admission webhook serving certificate, kept up to date in the background
+        let tls = if state.flags.webhook {
+            let certificates = CertificateManager::new(client.clone(), &state.flags);
+            let resolver = CertificateResolver::new(certificates.ensure().await?.certified_key()?);
+            let tls = resolver.server_config()?;
+            tokio::spawn(certificates.rotate(resolver));
+            Some(tls)
+        } else {
+            None
+        };
 
         // Start web server
-        let server = HttpServer::new(move || {
-            App::new()
-                .app_data(Data::new(state.clone()))
-                .wrap(middleware::Logger::default().exclude("/health"))
-                .service(index)
-                .service(health)
-                .service(metrics)
+        let server = HttpServer::new({
+            let state = state.clone();
+            move || {
+                App::new()
+                    .app_data(Data::new(state.clone()))
+                    .wrap(middleware::Logger::default().exclude("/health"))
+                    .service(index)
+                    .service(health)
+                    .service(metrics)
+            }
         })
         .bind("0.0.0.0:8443")?
         .shutdown_timeout(5)
         .run();
 
-        tokio::join!(
-            cluster_controller,
-            cluster_class_controller,
-            fleet_config_controller,
-            server
-        )
-        .3?;
+        // The admission webhook and the runtime extension hooks act on cluster objects,
+        // so they are only served over TLS, on a dedicated port
+        let trigger = state.hooks().clone();
+        let policies = state.policies().clone();
+        let webhook_server = match tls {
+            Some(tls) => Some(
+                HttpServer::new(move || {
+                    App::new()
+                        .app_data(Data::new(trigger.clone()))
+                        .app_data(Data::new(policies.clone()))
+                        .app_data(Data::new(client.clone()))
+                        .wrap(middleware::Logger::default())
+                        .service(webhook::validate_fleet_addon_config)
+                        .service(webhook::validate_fleet_addon_cluster_policy)
+                        .service(hooks::discovery)
+                        .service(hooks::after_control_plane_initialized)
+                        .service(hooks::before_cluster_delete)
+                        .service(hooks::before_cluster_upgrade)
+                        .service(hooks::after_cluster_upgrade)
+                })
+                .bind_rustls_0_23(("0.0.0.0", state.flags.webhook_port), tls)?
+                .shutdown_timeout(5)
+                .run(),
+            ),
+            None => None,
+        };
+
+        tokio::try_join!(
+            controllers,
+            async { Ok::<_, anyhow::Error>(server.await?) },
+            async {
+                if let Some(webhook_server) = webhook_server {
+                    webhook_server.await?;
+                }
+                Ok::<_, anyhow::Error>(())
+            }
+        )?;
     }
     Ok(())
 }
@@ -1,12 +1,15 @@
 use std::sync::Arc;
 
 use crate::Error;
+use crate::api::fleet_cluster::BundleSummary;
 use chrono::{DateTime, Utc};
 use kube::{
     Client, ResourceExt,
     runtime::events::{Recorder, Reporter},
 };
-use prometheus::{HistogramVec, IntCounter, IntCounterVec, Registry, histogram_opts, opts};
+use prometheus::{
+    HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Registry, histogram_opts, opts,
+};
 use serde::Serialize;
 use tokio::time::Instant;
 
@@ -15,6 +18,8 @@ pub struct Metrics {
     pub reconciliations: IntCounter,
     pub failures: IntCounterVec,
     pub reconcile_duration: HistogramVec,
+    pub leader: IntGauge,
+    pub bundle_deployments: IntGaugeVec,
 }
 
 impl Default for Metrics {
@@ -38,10 +43,25 @@ impl Default for Metrics {
         .unwrap();
         let reconciliations =
             IntCounter::new("caapf_controller_reconciliations_total", "reconciliations").unwrap();
+        let leader = IntGauge::new(
+            "caapf_controller_leader",
+            "Whether this instance currently holds the leader election lease",
+        )
+        .unwrap();
+        let bundle_deployments = IntGaugeVec::new(
+            opts!(
+                "caapf_cluster_bundle_deployments",
+                "Number of fleet bundle deployments per imported cluster and state",
+            ),
+            &["namespace", "cluster", "state"],
+        )
+        .unwrap();
         Metrics {
             reconciliations,
             failures,
             reconcile_duration,
+            leader,
+            bundle_deployments,
         }
     }
 }
@@ -60,6 +80,8 @@ impl Metrics {
         registry.register(Box::new(self.reconcile_duration.clone()))?;
         registry.register(Box::new(self.failures.clone()))?;
         registry.register(Box::new(self.reconciliations.clone()))?;
+        registry.register(Box::new(self.leader.clone()))?;
+        registry.register(Box::new(self.bundle_deployments.clone()))?;
         Ok(self)
     }
 
@@ -70,6 +92,25 @@ impl Metrics {
             .inc();
     }
 
+    /// Record the bundle deployment counts by state for the cluster.
+    pub fn set_bundle_deployments(&self, namespace: &str, cluster: &str, summary: &BundleSummary) {
+        for (state, count) in BundleSummary::STATES.into_iter().zip(summary.counts()) {
+            self.bundle_deployments
+                .with_label_values(&[namespace, cluster, state])
+                .set(count);
+        }
+    }
+
+    /// Stop reporting bundle deployment counts for the removed cluster.
+    pub fn remove_bundle_deployments(&self, namespace: &str, cluster: &str) {
+        for state in BundleSummary::STATES {
+            // The cluster may not have been reported yet
+            let _ = self
+                .bundle_deployments
+                .remove_label_values(&[namespace, cluster, state]);
+        }
+    }
+
     #[must_use]
     pub fn count_and_measure(&self) -> ReconcileMeasurer {
         self.reconciliations.inc();
@@ -0,0 +1,367 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use chrono::{DateTime, Datelike as _, Utc};
+use k8s_openapi::{
+    ByteString,
+    api::{admissionregistration::v1::ValidatingWebhookConfiguration, core::v1::Secret},
+};
+use kube::{
+    Api, Client, ResourceExt as _,
+    api::{ObjectMeta, PostParams},
+};
+use rcgen::{CertificateParams, DnType, KeyPair};
+use rustls::{
+    ServerConfig,
+    pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject as _},
+    server::{ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+};
+use thiserror::Error;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use crate::controller::{Flags, controller_namespace};
+
+pub static CERTIFICATE_EXPIRY_ANNOTATION: &str = "cert-expiry.fleet.addons.cluster.x-k8s.io";
+
+/// Validity of the generated serving certificate
+const CERTIFICATE_VALIDITY: chrono::Duration = chrono::Duration::days(365);
+
+/// Certificates are rotated once they are due to expire within this window
+const ROTATION_WINDOW: chrono::Duration = chrono::Duration::days(30);
+
+/// Interval between certificate expiry checks
+const ROTATION_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+pub type CertificateResult<T, E = CertificateError> = std::result::Result<T, E>;
+
+#[derive(Error, Debug)]
+pub enum CertificateError {
+    #[error("Certificate generation error: {0}")]
+    Generate(#[from] rcgen::Error),
+
+    #[error("Certificate PEM parse error: {0}")]
+    Pem(#[from] rustls::pki_types::pem::Error),
+
+    #[error("TLS configuration error: {0}")]
+    Tls(#[from] rustls::Error),
+
+    #[error("Certificate secret lookup error: {0}")]
+    Lookup(#[source] kube::Error),
+
+    #[error("Certificate secret store error: {0}")]
+    Store(#[source] kube::Error),
+
+    #[error("CA bundle injection error: {0}")]
+    CaBundle(#[source] kube::Error),
+}
+
+/// Self-signed serving certificate for the webhook service.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServingCertificate {
+    pub cert_pem: String,
+    pub key_pem: String,
+    pub not_after: DateTime<Utc>,
+}
+
+impl ServingCertificate {
+    /// Generate a certificate valid for the in-cluster DNS names of the webhook service.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the key or the certificate can't be generated.
+    pub fn generate(service: &str, namespace: &str) -> CertificateResult<Self> {
+        let mut params = CertificateParams::new(vec![
+            service.to_string(),
+            format!("{service}.{namespace}"),
+            format!("{service}.{namespace}.svc"),
+            format!("{service}.{namespace}.svc.cluster.local"),
+        ])?;
+        params
+            .distinguished_name
+            .push(DnType::CommonName, format!("{service}.{namespace}.svc"));
+
+        let now = Utc::now();
+        let not_after = now + CERTIFICATE_VALIDITY;
+        params.not_before = rcgen::date_time_ymd(now.year(), now.month() as u8, now.day() as u8);
+        params.not_after = rcgen::date_time_ymd(
+            not_after.year(),
+            not_after.month() as u8,
+            not_after.day() as u8,
+        );
+
+        let key_pair = KeyPair::generate()?;
+        let cert = params.self_signed(&key_pair)?;
+
+        Ok(Self {
+            cert_pem: cert.pem(),
+            key_pem: key_pair.serialize_pem(),
+            not_after,
+        })
+    }
+
+    fn from_secret(secret: &Secret) -> Option<Self> {
+        let data = secret.data.as_ref()?;
+        let ByteString(cert) = data.get("tls.crt")?;
+        let ByteString(key) = data.get("tls.key")?;
+        let not_after = secret.annotations().get(CERTIFICATE_EXPIRY_ANNOTATION)?;
+
+        Some(Self {
+            cert_pem: String::from_utf8(cert.clone()).ok()?,
+            key_pem: String::from_utf8(key.clone()).ok()?,
+            not_after: DateTime::parse_from_rfc3339(not_after).ok()?.into(),
+        })
+    }
+
+    fn apply_to(&self, secret: &mut Secret) {
+        secret.annotations_mut().insert(
+            CERTIFICATE_EXPIRY_ANNOTATION.to_string(),
+            self.not_after.to_rfc3339(),
+        );
+        secret.type_ = Some("kubernetes.io/tls".to_string());
+        secret.data = Some(BTreeMap::from([
+            (
+                "tls.crt".to_string(),
+                ByteString(self.cert_pem.clone().into_bytes()),
+            ),
+            (
+                "tls.key".to_string(),
+                ByteString(self.key_pem.clone().into_bytes()),
+            ),
+            // Self-signed, referenced for the Runtime Extension `ExtensionConfig` CA injection
+            (
+                "ca.crt".to_string(),
+                ByteString(self.cert_pem.clone().into_bytes()),
+            ),
+        ]));
+    }
+
+    fn needs_rotation(&self) -> bool {
+        self.not_after - ROTATION_WINDOW < Utc::now()
+    }
+
+    /// Parse the certificate into a rustls signing key.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the PEM content is not a supported key or certificate.
+    pub fn certified_key(&self) -> CertificateResult<Arc<CertifiedKey>> {
+        let cert = CertificateDer::from_pem_slice(self.cert_pem.as_bytes())?;
+        let key = PrivateKeyDer::from_pem_slice(self.key_pem.as_bytes())?;
+        let key = rustls::crypto::ring::sign::any_supported_type(&key)?;
+
+        Ok(Arc::new(CertifiedKey::new(vec![cert], key)))
+    }
+}
+
+/// `CertificateManager` keeps the serving certificate in a Secret shared between
+/// replicas, and publishes its CA bundle in the `ValidatingWebhookConfiguration`.
+pub struct CertificateManager {
+    secrets: Api<Secret>,
+    configurations: Api<ValidatingWebhookConfiguration>,
+    namespace: String,
+    service_name: String,
+    secret_name: String,
+    configuration_name: String,
+}
+
+impl CertificateManager {
+    #[must_use]
+    pub fn new(client: Client, flags: &Flags) -> Self {
+        let namespace = controller_namespace(&client);
+        Self {
+            secrets: Api::namespaced(client.clone(), &namespace),
+            configurations: Api::all(client),
+            namespace,
+            service_name: flags.webhook_service_name.clone(),
+            secret_name: flags.webhook_secret_name.clone(),
+            configuration_name: flags.webhook_configuration_name.clone(),
+        }
+    }
+
+    /// Returns a valid serving certificate, generating or rotating the stored one when
+    /// it is missing or about to expire, and injects it as the webhook CA bundle.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the certificate can't be generated, stored or published.
+    pub async fn ensure(&self) -> CertificateResult<ServingCertificate> {
+        let certificate = loop {
+            let existing = self
+                .secrets
+                .get_opt(&self.secret_name)
+                .await
+                .map_err(CertificateError::Lookup)?;
+
+            let stored = existing.as_ref().and_then(ServingCertificate::from_secret);
+            if let Some(certificate) = stored.filter(|c| !c.needs_rotation()) {
+                break certificate;
+            }
+
+            let certificate = ServingCertificate::generate(&self.service_name, &self.namespace)?;
+            let stored = match existing {
+                Some(mut secret) => {
+                    info!("Rotating webhook serving certificate {}", self.secret_name);
+                    certificate.apply_to(&mut secret);
+                    self.secrets
+                        .replace(&self.secret_name, &PostParams::default(), &secret)
+                        .await
+                }
+                None => {
+                    info!(
+                        "Generating webhook serving certificate {}",
+                        self.secret_name
+                    );
+                    let mut secret = Secret {
+                        metadata: ObjectMeta {
+                            name: Some(self.secret_name.clone()),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    };
+                    certificate.apply_to(&mut secret);
+                    self.secrets.create(&PostParams::default(), &secret).await
+                }
+            };
+
+            // Another replica stored a certificate concurrently, use that one instead
+            match stored {
+                Ok(_) => break certificate,
+                Err(kube::Error::Api(e)) if e.code == 409 => {}
+                Err(e) => return Err(CertificateError::Store(e)),
+            }
+        };
+
+        self.inject_ca_bundle(&certificate).await?;
+        Ok(certificate)
+    }
+
+    async fn inject_ca_bundle(&self, certificate: &ServingCertificate) -> CertificateResult<()> {
+        let Some(mut configuration) = self
+            .configurations
+            .get_opt(&self.configuration_name)
+            .await
+            .map_err(CertificateError::CaBundle)?
+        else {
+            warn!(
+                "ValidatingWebhookConfiguration {} not found, skipping CA bundle injection",
+                self.configuration_name
+            );
+            return Ok(());
+        };
+
+        let ca_bundle = ByteString(certificate.cert_pem.clone().into_bytes());
+        let webhooks = configuration.webhooks.get_or_insert_default();
+        if webhooks
+            .iter()
+            .all(|w| w.client_config.ca_bundle.as_ref() == Some(&ca_bundle))
+        {
+            return Ok(());
+        }
+
+        for webhook in webhooks {
+            webhook.client_config.ca_bundle = Some(ca_bundle.clone());
+        }
+
+        match self
+            .configurations
+            .replace(
+                &self.configuration_name,
+                &PostParams::default(),
+                &configuration,
+            )
+            .await
+        {
+            Ok(_) => Ok(()),
+            // Conflicting with another replica, injection is retried on the next check
+            Err(kube::Error::Api(e)) if e.code == 409 => Ok(()),
+            Err(e) => Err(CertificateError::CaBundle(e)),
+        }
+    }
+
+    /// Periodically checks the stored certificate, and swaps the served key once it was rotated.
+    pub async fn rotate(self, resolver: Arc<CertificateResolver>) {
+        loop {
+            sleep(ROTATION_CHECK_INTERVAL).await;
+            match self.ensure().await.and_then(|c| c.certified_key()) {
+                Ok(key) => resolver.update(key),
+                Err(e) => warn!("Failed to rotate webhook serving certificate: {e}"),
+            }
+        }
+    }
+}
+
+/// `CertificateResolver` serves the current certificate, allowing to rotate it
+/// without restarting the server.
+pub struct CertificateResolver(RwLock<Arc<CertifiedKey>>);
+
+impl CertificateResolver {
+    #[must_use]
+    pub fn new(key: Arc<CertifiedKey>) -> Arc<Self> {
+        Arc::new(Self(RwLock::new(key)))
+    }
+
+    fn update(&self, key: Arc<CertifiedKey>) {
+        if let Ok(mut current) = self.0.write() {
+            *current = key;
+        }
+    }
+
+    /// Build a TLS server configuration serving certificates from this resolver.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the default protocol versions are not supported.
+    pub fn server_config(self: &Arc<Self>) -> CertificateResult<ServerConfig> {
+        Ok(
+            ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()?
+                .with_no_client_auth()
+                .with_cert_resolver(self.clone()),
+        )
+    }
+}
+
+impl fmt::Debug for CertificateResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CertificateResolver")
+            .finish_non_exhaustive()
+    }
+}
+
+impl ResolvesServerCert for CertificateResolver {
+    fn resolve(&self, _: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        self.0.read().ok().map(|key| key.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::api::core::v1::Secret;
+
+    use super::ServingCertificate;
+
+    #[test]
+    fn test_certificate_secret_roundtrip() {
+        let certificate = ServingCertificate::generate("webhook-service", "system").unwrap();
+        assert!(!certificate.needs_rotation());
+        assert!(certificate.certified_key().is_ok());
+
+        let mut secret = Secret::default();
+        certificate.apply_to(&mut secret);
+        let stored = ServingCertificate::from_secret(&secret).unwrap();
+        assert_eq!(certificate.cert_pem, stored.cert_pem);
+        assert_eq!(certificate.key_pem, stored.key_pem);
+        assert_eq!(
+            certificate.not_after.timestamp(),
+            stored.not_after.timestamp()
+        );
+
+        assert!(ServingCertificate::from_secret(&Secret::default()).is_none());
+    }
+}
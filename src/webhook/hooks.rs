@@ -0,0 +1,885 @@
+use actix_web::{HttpResponse, Responder, post, web};
+use async_broadcast::{InactiveReceiver, Receiver, Sender};
+use chrono::{DateTime, TimeDelta, Utc};
+use k8s_openapi::api::core::v1::Namespace;
+use kube::{
+    Api, Client, Resource as _, ResourceExt as _,
+    api::{DeleteParams, ListParams, Patch, PatchParams},
+    runtime::{
+        events::{Event, EventType, Recorder},
+        reflector::{ObjectRef, Store},
+    },
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::{
+    api::{
+        capi_cluster::{CLUSTER_NAME_LABEL, Cluster, UPGRADE_GATE_ANNOTATION},
+        fleet_addon_cluster_policy::FleetAddonClusterPolicy,
+        fleet_addon_config::UpgradeGate,
+        fleet_bundle_deployment::BundleDeployment,
+        fleet_cluster::{
+            self, BUNDLE_DEPLOYMENT_CLUSTER_LABEL, BUNDLE_DEPLOYMENT_CLUSTER_NAMESPACE_LABEL,
+            BundleSummary,
+        },
+    },
+    controller::controller_namespace,
+    controllers::{
+        ConfigFetchError,
+        controller::{FLEET_FINALIZER, cached_policies, fetch_config},
+    },
+    metrics::Diagnostics,
+};
+
+pub static HOOKS_API_VERSION: &str = "hooks.runtime.cluster.x-k8s.io/v1alpha1";
+
+/// Name of every handler registered by the extension.
+pub static HANDLER_NAME: &str = "fleet-addon";
+
+/// Interval CAPI waits before calling a blocking hook again, while Fleet cleanup is running.
+const RETRY_AFTER_SECONDS: i32 = 5;
+
+/// Request of the lifecycle hooks. All hooks carry the cluster, upgrade hooks also carry
+/// the Kubernetes versions.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HookRequest {
+    pub cluster: Cluster,
+    #[serde(default)]
+    pub kubernetes_version: Option<String>,
+    #[serde(default)]
+    pub to_kubernetes_version: Option<String>,
+}
+
+#[derive(Error, Debug)]
+pub enum HookError {
+    #[error("Hook lookup error: {0}")]
+    Kube(#[from] kube::Error),
+
+    #[error("Hook config error: {0}")]
+    Config(#[from] ConfigFetchError),
+}
+
+pub type HookResult<T, E = HookError> = std::result::Result<T, E>;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookStatus {
+    Success,
+    Failure,
+}
+
+/// Response of the lifecycle hooks. Blocking hooks are called again by CAPI after
+/// `retryAfterSeconds`, if set.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HookResponse {
+    pub api_version: String,
+    pub kind: String,
+    pub status: HookStatus,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_seconds: Option<i32>,
+}
+
+impl HookResponse {
+    fn new(hook: &str, status: HookStatus, message: String) -> Self {
+        Self {
+            api_version: HOOKS_API_VERSION.into(),
+            kind: format!("{hook}Response"),
+            status,
+            message,
+            retry_after_seconds: None,
+        }
+    }
+
+    fn success(hook: &str) -> Self {
+        Self::new(hook, HookStatus::Success, String::new())
+    }
+
+    fn failure(hook: &str, message: String) -> Self {
+        Self::new(hook, HookStatus::Failure, message)
+    }
+
+    fn retry_after(mut self, seconds: i32) -> Self {
+        self.retry_after_seconds = Some(seconds);
+        self
+    }
+
+    fn with_message(mut self, message: String) -> Self {
+        self.message = message;
+        self
+    }
+}
+
+/// Hooks served by the extension, with the failure policy used by CAPI when a call fails.
+const HANDLERS: [(&str, &str); 4] = [
+    ("AfterControlPlaneInitialized", "Ignore"),
+    ("BeforeClusterDelete", "Fail"),
+    ("BeforeClusterUpgrade", "Ignore"),
+    ("AfterClusterUpgrade", "Ignore"),
+];
+
+/// Reconcile requests for CAPI clusters, sent by the lifecycle hooks to the cluster controller.
+#[derive(Clone)]
+pub struct HookTrigger {
+    tx: Sender<ObjectRef<Cluster>>,
+    // An inactive reader that keeps the channel open. Requests are dropped while
+    // the cluster controller is not running, e.g. on standby replicas.
+    rx: InactiveReceiver<ObjectRef<Cluster>>,
+}
+
+impl Default for HookTrigger {
+    fn default() -> Self {
+        let (mut tx, rx) = async_broadcast::broadcast(128);
+        tx.set_overflow(true);
+        Self {
+            tx,
+            rx: rx.deactivate(),
+        }
+    }
+}
+
+impl HookTrigger {
+    /// Request a reconcile of the cluster.
+    pub fn trigger(&self, cluster: &Cluster) {
+        if self.tx.try_broadcast(ObjectRef::from_obj(cluster)).is_err() {
+            info!(
+                "Cluster controller is not running, skipping reconcile of {}",
+                cluster.name_any()
+            );
+        }
+    }
+
+    /// Stream of reconcile requests for the cluster controller.
+    #[must_use]
+    pub fn subscribe(&self) -> Receiver<ObjectRef<Cluster>> {
+        self.rx.activate_cloned()
+    }
+}
+
+/// Runtime Extension discovery, listing the lifecycle hooks handled by the extension.
+#[post("/hooks.runtime.cluster.x-k8s.io/v1alpha1/discovery")]
+pub async fn discovery() -> impl Responder {
+    let handlers: Vec<_> = HANDLERS
+        .iter()
+        .map(|(hook, failure_policy)| {
+            serde_json::json!({
+                "name": HANDLER_NAME,
+                "requestHook": {"apiVersion": HOOKS_API_VERSION, "hook": hook},
+                "timeoutSeconds": 10,
+                "failurePolicy": failure_policy,
+            })
+        })
+        .collect();
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "apiVersion": HOOKS_API_VERSION,
+        "kind": "DiscoveryResponse",
+        "status": HookStatus::Success,
+        "handlers": handlers,
+    }))
+}
+
+/// Reconcile the cluster as soon as the control plane is initialized, instead of waiting
+/// for the status change to be observed.
+#[post("/hooks.runtime.cluster.x-k8s.io/v1alpha1/aftercontrolplaneinitialized/{name}")]
+pub async fn after_control_plane_initialized(
+    hooks: web::Data<HookTrigger>,
+    request: web::Json<HookRequest>,
+) -> impl Responder {
+    hooks.trigger(&request.cluster);
+    HttpResponse::Ok().json(HookResponse::success("AfterControlPlaneInitialized"))
+}
+
+/// Reconcile the cluster once an upgrade completes, resuming a Fleet cluster paused
+/// during the upgrade.
+#[post("/hooks.runtime.cluster.x-k8s.io/v1alpha1/afterclusterupgrade/{name}")]
+pub async fn after_cluster_upgrade(
+    hooks: web::Data<HookTrigger>,
+    request: web::Json<HookRequest>,
+) -> impl Responder {
+    hooks.trigger(&request.cluster);
+    HttpResponse::Ok().json(HookResponse::success("AfterClusterUpgrade"))
+}
+
+/// Hold back the topology upgrade until the Fleet cluster is ready, when the upgrade gate
+/// applies to the cluster.
+#[post("/hooks.runtime.cluster.x-k8s.io/v1alpha1/beforeclusterupgrade/{name}")]
+pub async fn before_cluster_upgrade(
+    client: web::Data<Client>,
+    policies: web::Data<Store<FleetAddonClusterPolicy>>,
+    request: web::Json<HookRequest>,
+) -> impl Responder {
+    let version = request.to_kubernetes_version.clone().unwrap_or_default();
+    let client = client.get_ref().clone();
+    let response = match check_upgrade_gate(client, &policies, &request.cluster, &version).await {
+        Ok(response) => response,
+        Err(e) => {
+            warn!(
+                "Upgrade gate check failed for {}: {e}",
+                request.cluster.name_any()
+            );
+            HookResponse::failure("BeforeClusterUpgrade", e.to_string())
+        }
+    };
+
+    HttpResponse::Ok().json(response)
+}
+
+/// Block the cluster deletion until the Fleet cluster imported from it is removed.
+#[post("/hooks.runtime.cluster.x-k8s.io/v1alpha1/beforeclusterdelete/{name}")]
+pub async fn before_cluster_delete(
+    client: web::Data<Client>,
+    request: web::Json<HookRequest>,
+) -> impl Responder {
+    let response = match cleanup_fleet_cluster(client.get_ref().clone(), &request.cluster).await {
+        Ok(true) => HookResponse::success("BeforeClusterDelete"),
+        Ok(false) => HookResponse::success("BeforeClusterDelete").retry_after(RETRY_AFTER_SECONDS),
+        Err(e) => {
+            warn!(
+                "Fleet cleanup failed for {}: {e}",
+                request.cluster.name_any()
+            );
+            HookResponse::failure("BeforeClusterDelete", e.to_string())
+        }
+    };
+
+    HttpResponse::Ok().json(response)
+}
+
+/// Delete the Fleet clusters owned by the CAPI cluster. Returns `true` once none are left.
+///
+/// The request only identifies the cluster. Its state is read from the API server, and nothing
+/// is deleted unless the cluster with the requested uid is being deleted.
+async fn cleanup_fleet_cluster(client: Client, request: &Cluster) -> kube::Result<bool> {
+    let namespace = request.namespace().unwrap_or_default();
+    let cluster = Api::<Cluster>::namespaced(client.clone(), &namespace)
+        .get_opt(&request.name_any())
+        .await?
+        .filter(|cluster| cluster.uid().is_some() && cluster.uid() == request.uid());
+    let Some(cluster) = cluster.filter(|cluster| cluster.metadata.deletion_timestamp.is_some())
+    else {
+        info!(
+            "Cluster {} is not being deleted, skipping Fleet cleanup",
+            request.name_any()
+        );
+        return Ok(true);
+    };
+
+    // Wait for the finalizer cleanup, which may drain the Fleet cluster first, and is held
+    // while the cluster is paused
+    if cluster.finalizers().iter().any(|f| f == FLEET_FINALIZER) {
+        return Ok(false);
+    }
+
+    // Without the finalizer, a paused cluster is being moved, Fleet objects are left in place
+    if cluster.paused() {
+        return Ok(true);
+    }
+
+    let api: Api<fleet_cluster::Cluster> = Api::namespaced(client, &namespace);
+    let owned: Vec<_> = api
+        .list(
+            &ListParams::default().labels(&format!("{CLUSTER_NAME_LABEL}={}", cluster.name_any())),
+        )
+        .await?
+        .items
+        .into_iter()
+        .filter(|fleet| {
+            fleet
+                .owner_references()
+                .iter()
+                .any(|owner| Some(&owner.uid) == cluster.uid().as_ref())
+        })
+        .collect();
+
+    for fleet in &owned {
+        if fleet.metadata.deletion_timestamp.is_none() {
+            info!("Deleting fleet cluster {}", fleet.name_any());
+            api.delete(&fleet.name_any(), &DeleteParams::default())
+                .await?;
+        }
+    }
+
+    Ok(owned.is_empty())
+}
+
+/// Upgrade gate applying to the cluster, from the `FleetAddonConfig` cluster settings
+/// and the cluster policy.
+async fn upgrade_gate(
+    client: Client,
+    policies: &Store<FleetAddonClusterPolicy>,
+    cluster: &Cluster,
+) -> HookResult<Option<UpgradeGate>> {
+    let config = fetch_config(client.clone()).await?;
+    let Some(mut cluster_config) = config.spec.cluster else {
+        return Ok(None);
+    };
+
+    let policies = cached_policies(policies).await?;
+    if !policies.is_empty() {
+        let controller_namespace = controller_namespace(&client);
+        let ns_labels = Api::<Namespace>::all(client)
+            .get_metadata_opt(&cluster.namespace().unwrap_or_default())
+            .await?
+            .map(|ns| ns.labels().clone())
+            .unwrap_or_default();
+        if let Some(policy) =
+            FleetAddonClusterPolicy::resolve(&policies, cluster, &ns_labels, &controller_namespace)
+        {
+            policy.spec.cluster.apply(&mut cluster_config);
+        }
+    }
+
+    Ok(cluster_config
+        .upgrade_gate
+        .filter(|gate| gate.applies_to(cluster.cluster_class_name())))
+}
+
+/// Start of the gate for the target version, recorded as `<version>@<timestamp>`.
+fn gate_started(cluster: &Cluster, version: &str) -> Option<DateTime<Utc>> {
+    let (gated_version, started) = cluster
+        .annotations()
+        .get(UPGRADE_GATE_ANNOTATION)?
+        .split_once('@')?;
+    if gated_version != version {
+        return None;
+    }
+
+    DateTime::parse_from_rfc3339(started).ok().map(Into::into)
+}
+
+/// Check the Fleet cluster readiness for the upgrade. The gate start is recorded on the
+/// CAPI cluster, so the timeout is kept across retries and controller restarts.
+async fn check_upgrade_gate(
+    client: Client,
+    policies: &Store<FleetAddonClusterPolicy>,
+    cluster: &Cluster,
+    version: &str,
+) -> HookResult<HookResponse> {
+    const HOOK: &str = "BeforeClusterUpgrade";
+
+    let Some(gate) = upgrade_gate(client.clone(), policies, cluster).await? else {
+        return Ok(HookResponse::success(HOOK));
+    };
+
+    let namespace = cluster.namespace().unwrap_or_default();
+    let fleet = Api::<fleet_cluster::Cluster>::namespaced(client.clone(), &namespace)
+        .list(
+            &ListParams::default().labels(&format!("{CLUSTER_NAME_LABEL}={}", cluster.name_any())),
+        )
+        .await?
+        .items
+        .into_iter()
+        .next();
+    // Clusters not imported into Fleet have no addons to wait for
+    let Some(fleet) = fleet else {
+        return Ok(HookResponse::success(HOOK));
+    };
+
+    let deployments = Api::<BundleDeployment>::all(client.clone())
+        .list(&ListParams::default().labels(&format!(
+            "{BUNDLE_DEPLOYMENT_CLUSTER_LABEL}={},{BUNDLE_DEPLOYMENT_CLUSTER_NAMESPACE_LABEL}={}",
+            fleet.name_any(),
+            fleet.namespace().unwrap_or_default()
+        )))
+        .await?
+        .items;
+    let summary = BundleSummary::from_deployments(&deployments);
+    let ready = fleet.agent_registered() && summary.ready == summary.total();
+
+    let clusters = Api::<Cluster>::namespaced(client.clone(), &namespace);
+    let started = gate_started(cluster, version);
+    let record = |value: Option<String>| {
+        Patch::Merge(json!({"metadata": {"annotations": {UPGRADE_GATE_ANNOTATION: value}}}))
+    };
+
+    if ready {
+        if started.is_some() {
+            clusters
+                .patch_metadata(&cluster.name_any(), &PatchParams::default(), &record(None))
+                .await?;
+        }
+        return Ok(HookResponse::success(HOOK));
+    }
+
+    let status = if fleet.agent_registered() {
+        summary.condition().message.unwrap_or_default()
+    } else {
+        "fleet agent is not connected".to_string()
+    };
+    let message = format!(
+        "Waiting for fleet cluster `{}` to be ready before the upgrade to {version}: {status}",
+        fleet.name_any(),
+    );
+    let Some(started) = started else {
+        let now = Utc::now().to_rfc3339();
+        clusters
+            .patch_metadata(
+                &cluster.name_any(),
+                &PatchParams::default(),
+                &record(Some(format!("{version}@{now}"))),
+            )
+            .await?;
+        return Ok(HookResponse::success(HOOK)
+            .retry_after(gate.retry_after_seconds())
+            .with_message(message));
+    };
+
+    if Utc::now() - started < TimeDelta::seconds(gate.timeout_seconds()) {
+        return Ok(HookResponse::success(HOOK)
+            .retry_after(gate.retry_after_seconds())
+            .with_message(message));
+    }
+
+    // The gate timed out, the upgrade proceeds and the override is reported
+    let note = format!(
+        "Upgrade gate timed out after {}s, proceeding with the upgrade to {version}. {message}",
+        gate.timeout_seconds()
+    );
+    warn!("{note}");
+    Recorder::new(client, Diagnostics::default().reporter)
+        .publish(
+            &Event {
+                type_: EventType::Warning,
+                reason: "UpgradeGateTimedOut".into(),
+                note: Some(note.clone()),
+                action: "Upgrading".into(),
+                secondary: Some(fleet.object_ref(&())),
+            },
+            &cluster.object_ref(&()),
+        )
+        .await?;
+    clusters
+        .patch_metadata(&cluster.name_any(), &PatchParams::default(), &record(None))
+        .await?;
+
+    Ok(HookResponse::success(HOOK).with_message(note))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        App,
+        test::{TestRequest, call_and_read_body_json, init_service},
+        web::Data,
+    };
+    use chrono::{TimeDelta, Utc};
+    use futures::StreamExt as _;
+    use http::{Method, Request, Response};
+    use kube::{
+        Client,
+        client::Body,
+        runtime::{
+            reflector::{Store, store::Writer},
+            watcher,
+        },
+    };
+    use serde_json::{Value, json};
+    use tokio::task::JoinHandle;
+
+    use crate::{
+        api::{
+            capi_cluster::{Cluster, UPGRADE_GATE_ANNOTATION},
+            fleet_addon_cluster_policy::FleetAddonClusterPolicy,
+            fleet_addon_config::UpgradeGate,
+        },
+        controllers::controller::FLEET_FINALIZER,
+    };
+
+    use super::{
+        HookResponse, HookStatus, HookTrigger, after_control_plane_initialized,
+        before_cluster_delete, check_upgrade_gate, discovery, gate_started,
+    };
+
+    fn hook_request(hook: &str) -> Value {
+        json!({
+            "apiVersion": "hooks.runtime.cluster.x-k8s.io/v1alpha1",
+            "kind": format!("{hook}Request"),
+            "cluster": {
+                "apiVersion": "cluster.x-k8s.io/v1beta1",
+                "kind": "Cluster",
+                "metadata": {"name": "test", "namespace": "default", "uid": "1234"},
+                "spec": {},
+            },
+        })
+    }
+
+    #[actix_web::test]
+    async fn test_discovery() {
+        let app = init_service(App::new().service(discovery)).await;
+        let request = TestRequest::post()
+            .uri("/hooks.runtime.cluster.x-k8s.io/v1alpha1/discovery")
+            .set_json(json!({
+                "apiVersion": "hooks.runtime.cluster.x-k8s.io/v1alpha1",
+                "kind": "DiscoveryRequest",
+            }))
+            .to_request();
+        let response: Value = call_and_read_body_json(&app, request).await;
+
+        assert_eq!(response["status"], "Success");
+        let hooks: Vec<_> = response["handlers"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|h| h["requestHook"]["hook"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            hooks,
+            [
+                "AfterControlPlaneInitialized",
+                "BeforeClusterDelete",
+                "BeforeClusterUpgrade",
+                "AfterClusterUpgrade"
+            ]
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_after_control_plane_initialized() {
+        let hooks = HookTrigger::default();
+        let mut reconciles = hooks.subscribe();
+        let app = init_service(
+            App::new()
+                .app_data(Data::new(hooks))
+                .service(after_control_plane_initialized),
+        )
+        .await;
+
+        let request = TestRequest::post()
+            .uri(
+                "/hooks.runtime.cluster.x-k8s.io/v1alpha1/aftercontrolplaneinitialized/fleet-addon",
+            )
+            .set_json(hook_request("AfterControlPlaneInitialized"))
+            .to_request();
+        let response: HookResponse = call_and_read_body_json(&app, request).await;
+
+        assert_eq!(response.status, HookStatus::Success);
+        assert_eq!(response.kind, "AfterControlPlaneInitializedResponse");
+        assert_eq!(reconciles.next().await.unwrap().name, "test");
+    }
+
+    /// Live CAPI cluster with the requested name and uid, being deleted.
+    fn deleting_cluster() -> Value {
+        json!({
+            "apiVersion": "cluster.x-k8s.io/v1beta1",
+            "kind": "Cluster",
+            "metadata": {
+                "name": "test",
+                "namespace": "default",
+                "uid": "1234",
+                "deletionTimestamp": "2026-01-01T00:00:00Z",
+            },
+            "spec": {},
+        })
+    }
+
+    /// Client serving the live CAPI cluster and a Fleet cluster owned by it.
+    fn delete_client(live: Value) -> (Client, JoinHandle<Vec<(Method, String)>>) {
+        let (service, mut handle) = tower_test::mock::pair::<Request<Body>, Response<Body>>();
+        let server = tokio::spawn(async move {
+            let mut served = vec![];
+            while let Some((request, send)) = handle.next_request().await {
+                let method = request.method().clone();
+                let path = request.uri().path().to_string();
+                let (status, body) = if path.starts_with("/apis/cluster.x-k8s.io/") {
+                    match live.get("code").and_then(Value::as_u64) {
+                        Some(code) => (u16::try_from(code).unwrap(), live.clone()),
+                        None => (200, live.clone()),
+                    }
+                } else if method == Method::DELETE {
+                    (200, json!({"kind": "Status", "status": "Success"}))
+                } else {
+                    let fleet = json!([{
+                        "apiVersion": "fleet.cattle.io/v1alpha1",
+                        "kind": "Cluster",
+                        "metadata": {
+                            "name": "test",
+                            "namespace": "default",
+                            "ownerReferences": [{
+                                "apiVersion": "cluster.x-k8s.io/v1beta1",
+                                "kind": "Cluster",
+                                "name": "test",
+                                "uid": "1234",
+                            }],
+                        },
+                        "spec": {},
+                    }]);
+                    (200, list("ClusterList", fleet))
+                };
+                send.send_response(
+                    Response::builder()
+                        .status(status)
+                        .body(Body::from(serde_json::to_vec(&body).unwrap()))
+                        .unwrap(),
+                );
+                served.push((method, path));
+            }
+            served
+        });
+
+        (Client::new(service, "default"), server)
+    }
+
+    async fn call_before_cluster_delete(client: Client, request: &Value) -> HookResponse {
+        let app = init_service(
+            App::new()
+                .app_data(Data::new(client))
+                .service(before_cluster_delete),
+        )
+        .await;
+        let request = TestRequest::post()
+            .uri("/hooks.runtime.cluster.x-k8s.io/v1alpha1/beforeclusterdelete/fleet-addon")
+            .set_json(request)
+            .to_request();
+        call_and_read_body_json(&app, request).await
+    }
+
+    #[actix_web::test]
+    async fn test_before_cluster_delete() {
+        let (client, server) = delete_client(deleting_cluster());
+        let response =
+            call_before_cluster_delete(client, &hook_request("BeforeClusterDelete")).await;
+        let served = server.await.unwrap();
+
+        // Deletion is blocked until the Fleet cluster is removed
+        assert_eq!(response.status, HookStatus::Success);
+        assert_eq!(response.retry_after_seconds, Some(5));
+        let methods: Vec<_> = served.iter().map(|(method, _)| method.clone()).collect();
+        assert_eq!(methods, [Method::GET, Method::GET, Method::DELETE]);
+        assert!(served[2].1.ends_with("/namespaces/default/clusters/test"));
+    }
+
+    #[actix_web::test]
+    async fn test_before_cluster_delete_paused() {
+        let mut live = deleting_cluster();
+        live["spec"]["paused"] = json!(true);
+        live["metadata"]["finalizers"] = json!([FLEET_FINALIZER]);
+
+        // The finalizer holds the deletion of the paused cluster until it resumes
+        let (client, server) = delete_client(live.clone());
+        let response =
+            call_before_cluster_delete(client, &hook_request("BeforeClusterDelete")).await;
+        assert_eq!(response.status, HookStatus::Success);
+        assert_eq!(response.retry_after_seconds, Some(5));
+        assert_eq!(server.await.unwrap().len(), 1);
+
+        // Once the finalizer is removed by `clusterctl move`, Fleet objects are left in place
+        live["metadata"]["finalizers"] = json!([]);
+        let (client, server) = delete_client(live);
+        let response =
+            call_before_cluster_delete(client, &hook_request("BeforeClusterDelete")).await;
+        assert_eq!(response.status, HookStatus::Success);
+        assert_eq!(response.retry_after_seconds, None);
+        assert_eq!(server.await.unwrap().len(), 1);
+    }
+
+    #[actix_web::test]
+    async fn test_before_cluster_delete_untrusted() {
+        // The request claims a deleted cluster without finalizers, which is not trusted
+        let mut request = hook_request("BeforeClusterDelete");
+        request["cluster"]["metadata"]["deletionTimestamp"] = json!("2026-01-01T00:00:00Z");
+
+        let mut not_deleting = deleting_cluster();
+        not_deleting["metadata"]
+            .as_object_mut()
+            .unwrap()
+            .remove("deletionTimestamp");
+        let mut other_uid = deleting_cluster();
+        other_uid["metadata"]["uid"] = json!("5678");
+        let missing = json!({
+            "apiVersion": "v1",
+            "kind": "Status",
+            "status": "Failure",
+            "reason": "NotFound",
+            "code": 404,
+        });
+
+        for live in [not_deleting, other_uid, missing] {
+            let (client, server) = delete_client(live);
+            let response = call_before_cluster_delete(client, &request).await;
+            assert_eq!(response.status, HookStatus::Success);
+            assert_eq!(response.retry_after_seconds, None);
+
+            // Only the live cluster is read, no Fleet cluster is listed or deleted
+            let served = server.await.unwrap();
+            assert_eq!(served.len(), 1);
+            assert_eq!(served[0].0, Method::GET);
+        }
+    }
+
+    #[test]
+    fn test_upgrade_gate() {
+        let gate = UpgradeGate {
+            cluster_classes: Some(vec!["quick-start".into()]),
+            ..Default::default()
+        };
+        assert!(gate.applies_to(Some("quick-start")));
+        assert!(!gate.applies_to(Some("other")));
+        assert!(!gate.applies_to(None));
+        assert!(UpgradeGate::default().applies_to(None));
+        assert_eq!(gate.timeout_seconds(), 3600);
+
+        let cluster: Cluster = serde_json::from_value(json!({
+            "metadata": {
+                "name": "test",
+                "annotations": {UPGRADE_GATE_ANNOTATION: "v1.33.0@2026-01-01T00:00:00+00:00"},
+            },
+        }))
+        .unwrap();
+        assert_eq!(
+            gate_started(&cluster, "v1.33.0").map(|t| t.to_rfc3339()),
+            Some("2026-01-01T00:00:00+00:00".into())
+        );
+        // A gate recorded for a previous upgrade is restarted
+        assert!(gate_started(&cluster, "v1.34.0").is_none());
+    }
+
+    fn list(kind: &str, items: Value) -> Value {
+        json!({"kind": kind, "metadata": {}, "items": items})
+    }
+
+    /// Client serving a gated cluster config and a Fleet cluster with an unregistered agent.
+    fn gate_client() -> (Client, JoinHandle<Vec<(Method, String)>>) {
+        let (service, mut handle) = tower_test::mock::pair::<Request<Body>, Response<Body>>();
+        let server = tokio::spawn(async move {
+            let mut served = vec![];
+            while let Some((request, send)) = handle.next_request().await {
+                let path = request.uri().path().to_string();
+                let body = match path.rsplit('/').next().unwrap_or_default() {
+                    "fleet-addon-config" => json!({
+                        "apiVersion": "addons.cluster.x-k8s.io/v1alpha1",
+                        "kind": "FleetAddonConfig",
+                        "metadata": {"name": "fleet-addon-config"},
+                        "spec": {"cluster": {
+                            "namespaceSelector": {},
+                            "selector": {},
+                            "upgradeGate": {"timeoutSeconds": 600, "retryAfterSeconds": 10},
+                        }},
+                    }),
+                    "clusters" => list(
+                        "ClusterList",
+                        json!([{
+                            "apiVersion": "fleet.cattle.io/v1alpha1",
+                            "kind": "Cluster",
+                            "metadata": {"name": "test", "namespace": "default"},
+                            "spec": {},
+                        }]),
+                    ),
+                    "bundledeployments" => list("BundleDeploymentList", json!([])),
+                    _ if path.contains("/events") => json!({
+                        "apiVersion": "events.k8s.io/v1",
+                        "kind": "Event",
+                        "metadata": {"name": "test", "namespace": "default"},
+                        "eventTime": "2026-01-01T00:00:00.000000Z",
+                    }),
+                    _ => json!({
+                        "apiVersion": "cluster.x-k8s.io/v1beta1",
+                        "kind": "Cluster",
+                        "metadata": {"name": "test", "namespace": "default"},
+                    }),
+                };
+                send.send_response(
+                    Response::builder()
+                        .body(Body::from(serde_json::to_vec(&body).unwrap()))
+                        .unwrap(),
+                );
+                served.push((request.method().clone(), path));
+            }
+            served
+        });
+
+        (Client::new(service, "default"), server)
+    }
+
+    /// Synced store without cluster policies.
+    fn policy_store() -> Store<FleetAddonClusterPolicy> {
+        let mut writer = Writer::default();
+        writer.apply_watcher_event(&watcher::Event::InitDone);
+        writer.as_reader()
+    }
+
+    fn gated_cluster(started: Option<String>) -> Cluster {
+        serde_json::from_value(json!({
+            "metadata": {
+                "name": "test",
+                "namespace": "default",
+                "annotations": started
+                    .map(|started| json!({UPGRADE_GATE_ANNOTATION: format!("v1.33.0@{started}")}))
+                    .unwrap_or_default(),
+            },
+            "spec": {},
+        }))
+        .unwrap()
+    }
+
+    fn patched(served: &[(Method, String)]) -> bool {
+        served
+            .iter()
+            .any(|(method, path)| *method == Method::PATCH && path.ends_with("/clusters/test"))
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_gate_record() {
+        // The gate start is recorded on the first check, and the upgrade is held
+        let (client, server) = gate_client();
+        let response = check_upgrade_gate(client, &policy_store(), &gated_cluster(None), "v1.33.0")
+            .await
+            .unwrap();
+        let served = server.await.unwrap();
+
+        assert_eq!(response.status, HookStatus::Success);
+        assert_eq!(response.retry_after_seconds, Some(10));
+        assert!(response.message.contains("fleet agent is not connected"));
+        assert!(patched(&served));
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_gate_retry() {
+        // A recorded gate within the timeout keeps holding the upgrade without updates
+        let started = (Utc::now() - TimeDelta::seconds(60)).to_rfc3339();
+        let (client, server) = gate_client();
+        let response = check_upgrade_gate(
+            client,
+            &policy_store(),
+            &gated_cluster(Some(started)),
+            "v1.33.0",
+        )
+        .await
+        .unwrap();
+        let served = server.await.unwrap();
+
+        assert_eq!(response.retry_after_seconds, Some(10));
+        assert!(!patched(&served));
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_gate_timeout() {
+        // Once timed out, the upgrade proceeds, the override is reported and the record cleared
+        let started = (Utc::now() - TimeDelta::seconds(601)).to_rfc3339();
+        let (client, server) = gate_client();
+        let response = check_upgrade_gate(
+            client,
+            &policy_store(),
+            &gated_cluster(Some(started)),
+            "v1.33.0",
+        )
+        .await
+        .unwrap();
+        let served = server.await.unwrap();
+
+        assert_eq!(response.status, HookStatus::Success);
+        assert_eq!(response.retry_after_seconds, None);
+        assert!(
+            response
+                .message
+                .starts_with("Upgrade gate timed out after 600s")
+        );
+        assert!(served.iter().any(|(_, path)| path.contains("/events")));
+        assert!(patched(&served));
+    }
+}
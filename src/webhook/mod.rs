@@ -0,0 +1,70 @@
+use actix_web::{HttpResponse, Responder, post, web};
+use kube::{
+    Client,
+    core::admission::{AdmissionRequest, AdmissionResponse, AdmissionReview},
+};
+use tracing::{info, warn};
+
+use crate::{
+    api::{
+        fleet_addon_cluster_policy::FleetAddonClusterPolicy, fleet_addon_config::FleetAddonConfig,
+    },
+    controller::controller_namespace,
+};
+
+pub mod certificate;
+pub mod hooks;
+
+/// Validating admission webhook for the `FleetAddonConfig` resource
+#[post("/validate-addons-cluster-x-k8s-io-v1alpha1-fleetaddonconfig")]
+pub async fn validate_fleet_addon_config(
+    review: web::Json<AdmissionReview<FleetAddonConfig>>,
+) -> impl Responder {
+    let request: AdmissionRequest<FleetAddonConfig> = match review.into_inner().try_into() {
+        Ok(request) => request,
+        Err(e) => {
+            warn!("Invalid admission review: {e}");
+            return HttpResponse::BadRequest()
+                .json(AdmissionResponse::invalid(e.to_string()).into_review());
+        }
+    };
+
+    let mut response = AdmissionResponse::from(&request);
+    if let Some(config) = request.object.as_ref() {
+        if let Err(e) = config.validate() {
+            info!("Rejected FleetAddonConfig {}: {e}", request.name);
+            response = response.deny(e);
+        }
+    }
+
+    HttpResponse::Ok().json(response.into_review())
+}
+
+/// Validating admission webhook for the `FleetAddonClusterPolicy` resource
+#[post("/validate-addons-cluster-x-k8s-io-v1alpha1-fleetaddonclusterpolicy")]
+pub async fn validate_fleet_addon_cluster_policy(
+    review: web::Json<AdmissionReview<FleetAddonClusterPolicy>>,
+    client: web::Data<Client>,
+) -> impl Responder {
+    let request: AdmissionRequest<FleetAddonClusterPolicy> = match review.into_inner().try_into() {
+        Ok(request) => request,
+        Err(e) => {
+            warn!("Invalid admission review: {e}");
+            return HttpResponse::BadRequest()
+                .json(AdmissionResponse::invalid(e.to_string()).into_review());
+        }
+    };
+
+    let mut response = AdmissionResponse::from(&request);
+    if let Some(policy) = request.object.as_ref() {
+        if let Err(e) = policy.validate(&controller_namespace(&client)) {
+            info!(
+                "Rejected FleetAddonClusterPolicy {}: {e}",
+                policy.reference()
+            );
+            response = response.deny(e);
+        }
+    }
+
+    HttpResponse::Ok().json(response.into_review())
+}